@@ -0,0 +1,152 @@
+// A stand-in for the board, to try `upload` without one. It opens a
+// pseudo-terminal, prints its path, and answers like `bin/loader.rs`:
+//
+//     cargo run --bin fake_loader
+//     cargo run --bin upload -- /dev/pts/N some.bin
+//
+// Received programs are not run, their size and CRC are printed instead.
+// Like the hardware, more than 128 unread bytes is an overflow.
+
+use std::io::{Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use clap::Parser;
+use serialport::{SerialPort, TTYPort};
+
+use cpu_host::upload::FIFO_SIZE;
+use cpu_link::crc::crc16;
+
+#[derive(Parser)]
+#[command(about = "Pretend to be the board's loader on a pseudo-terminal")]
+struct Args {
+    /// how long to take for each received byte, in microseconds, to model a slow board
+    #[arg(long, default_value_t = 0)]
+    byte_time: u64,
+}
+
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+struct Board {
+    port: TTYPort,
+    byte_time: Duration,
+    overflows: usize,
+}
+
+impl Board {
+    fn print(&mut self, s: &str) -> Result<()> {
+        self.port.write_all(s.as_bytes())?;
+        Ok(())
+    }
+
+    fn read_byte(&mut self, timeout: Duration) -> Result<Option<u8>> {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            let pending = self.port.bytes_to_read()? as usize;
+            if pending > FIFO_SIZE {
+                self.overflows += 1;
+                eprintln!("[fake] FIFO overflow: {} bytes waiting", pending);
+            }
+            let mut byte = [0];
+            match self.port.read(&mut byte) {
+                Ok(1) => {
+                    thread::sleep(self.byte_time);
+                    return Ok(Some(byte[0]));
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        loop {
+            let Some(c) = self.read_byte(Duration::from_secs(3600))? else {
+                continue;
+            };
+            // echo, like `console::read_line`
+            self.port.write_all(&[c])?;
+            match c {
+                b'\r' => {
+                    self.port.write_all(b"\n")?;
+                    return Ok(line);
+                }
+                b'\x7f' => {
+                    line.pop();
+                }
+                c => line.push(c as char),
+            }
+        }
+    }
+
+    fn receive(&mut self, size: usize) -> Result<Option<Vec<u8>>> {
+        let mut data = Vec::with_capacity(size);
+        while data.len() < size {
+            match self.read_byte(RECEIVE_TIMEOUT)? {
+                Some(byte) => data.push(byte),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(data))
+    }
+
+    fn run(&mut self) -> Result<()> {
+        self.print("[loader] Welcome to the program loader!\r\n")?;
+        loop {
+            self.print("[loader] > ")?;
+            let line = self.read_line()?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Ok(size) = line.parse::<usize>() else {
+                self.print("[loader] Invalid command! Type h for help\r\n")?;
+                continue;
+            };
+
+            self.print("[loader] Transfer the data below\r\n")?;
+            self.overflows = 0;
+            let Some(program) = self.receive(size)? else {
+                self.print(&format!(
+                    "[loader] Transfer timed out after some of {} bytes\r\n",
+                    size
+                ))?;
+                continue;
+            };
+            self.print("[loader] Program loaded!\r\n")?;
+            let report = format!(
+                "[fake] Received {} bytes, CRC-16 {:#06x}, {} overflows\r\n",
+                program.len(),
+                crc16(&program),
+                self.overflows
+            );
+            eprint!("{}", report);
+            self.print(&report)?;
+            self.print("[loader] Program exited\r\n\r\n")?;
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let (mut port, other) = TTYPort::pair()?;
+    port.set_timeout(Duration::from_millis(20))?;
+    println!(
+        "[fake] Listening on {}",
+        other.name().unwrap_or_else(|| "an unnamed pty".into())
+    );
+
+    let mut board = Board {
+        port,
+        byte_time: Duration::from_micros(args.byte_time),
+        overflows: 0,
+    };
+    let result = board.run();
+    // keep the other end open until here, or the pty goes away
+    drop(other);
+    result
+}
@@ -0,0 +1,80 @@
+// Uploads a program to the loader, then attaches a terminal:
+//
+//     cargo run --release --bin upload -- /dev/ttyUSB0 ../program/bin/tetris.bin
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use cpu_host::terminal;
+use cpu_host::upload::{self, Options, FIFO_SIZE};
+
+#[derive(Parser)]
+#[command(about = "Upload a program to the board's loader")]
+struct Args {
+    /// the serial port of the board
+    port: String,
+    /// the raw binary, as made by `make`
+    program: PathBuf,
+    #[arg(short, long, default_value_t = 115200)]
+    baud_rate: u32,
+    /// bytes sent at once, at most 128 (the size of the board's FIFO)
+    #[arg(long, default_value_t = FIFO_SIZE / 2)]
+    chunk_size: usize,
+    /// extra pause after each chunk, in milliseconds
+    #[arg(long, default_value_t = 0)]
+    chunk_delay: u64,
+    /// how long to wait for the loader, in seconds
+    #[arg(long, default_value_t = 10)]
+    timeout: u64,
+    /// exit after uploading, instead of attaching a terminal
+    #[arg(long)]
+    no_terminal: bool,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    anyhow::ensure!(
+        (1..=FIFO_SIZE).contains(&args.chunk_size),
+        "the chunk size must be between 1 and {}",
+        FIFO_SIZE
+    );
+
+    let program = std::fs::read(&args.program)
+        .with_context(|| format!("cannot read {}", args.program.display()))?;
+    let mut port = serialport::new(&args.port, args.baud_rate)
+        .timeout(Duration::from_millis(20))
+        .open()
+        .with_context(|| format!("cannot open {}", args.port))?;
+
+    let options = Options {
+        baud_rate: args.baud_rate,
+        chunk_size: args.chunk_size,
+        chunk_delay: Duration::from_millis(args.chunk_delay),
+        prompt_timeout: Duration::from_secs(args.timeout),
+    };
+
+    eprintln!("[upload] Waiting for the loader on {}", args.port);
+    upload::wait_for_prompt(&mut port, options.prompt_timeout)
+        .context("the loader is not answering, try the reset button")?;
+
+    let mut shown = None;
+    upload::upload(&mut port, &program, &options, |sent, total| {
+        let percent = (sent * 100).checked_div(total).unwrap_or(100);
+        if shown != Some(percent) {
+            shown = Some(percent);
+            eprint!("\r[upload] {:>7} / {} bytes ({:>3}%)", sent, total, percent);
+            let _ = std::io::stderr().flush();
+        }
+    })
+    .context("upload failed")?;
+    eprintln!("\n[upload] Done");
+
+    if !args.no_terminal {
+        terminal::attach(port)?;
+    }
+    Ok(())
+}
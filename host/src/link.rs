@@ -0,0 +1,62 @@
+// The host end of `cpu_lib::link`, over anything that reads and writes bytes,
+// e.g. a serial port opened with a short read timeout.
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::time::{Duration, Instant};
+
+pub use cpu_link::{Config, Stats, Transport, MAX_PAYLOAD};
+
+pub type Error = cpu_link::Error<io::Error>;
+
+pub type Link<S> = cpu_link::Endpoint<Stream<S>>;
+
+pub struct Stream<S> {
+    inner: S,
+    start: Instant,
+}
+
+impl<S: Read + Write> Stream<S> {
+    pub fn new(inner: S) -> Stream<S> {
+        Stream {
+            inner,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Read + Write> Transport for Stream<S> {
+    type Error = io::Error;
+
+    // a read timeout counts as nothing arrived
+    fn read(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0];
+        match self.inner.read(&mut byte) {
+            Ok(0) => Err(ErrorKind::UnexpectedEof.into()),
+            Ok(_) => Ok(Some(byte[0])),
+            Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => Ok(None),
+            Err(e) if e.kind() == ErrorKind::Interrupted => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.inner.write_all(data)?;
+        self.inner.flush()
+    }
+
+    fn now(&mut self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+pub fn open<S: Read + Write>(inner: S) -> Link<S> {
+    Link::new(Stream::new(inner))
+}
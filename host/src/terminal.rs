@@ -0,0 +1,123 @@
+// A minimal serial terminal: the board's output goes to stdout, keys go to
+// the board. Ctrl-] quits.
+
+use std::io::{self, Write};
+use std::thread;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use serialport::SerialPort;
+
+// what the board expects for a key, as sent by a VT100 terminal
+fn encode_key(key: &KeyEvent) -> Option<Vec<u8>> {
+    let bytes: &[u8] = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            return c
+                .is_ascii_alphabetic()
+                .then(|| vec![c.to_ascii_lowercase() as u8 - b'a' + 1]);
+        }
+        KeyCode::Char(c) => return Some(c.to_string().into_bytes()),
+        // the loader and the console end lines with CR
+        KeyCode::Enter => b"\r",
+        KeyCode::Backspace => b"\x7f",
+        KeyCode::Tab => b"\t",
+        KeyCode::Esc => b"\x1b",
+        KeyCode::Up => b"\x1b[A",
+        KeyCode::Down => b"\x1b[B",
+        KeyCode::Right => b"\x1b[C",
+        KeyCode::Left => b"\x1b[D",
+        KeyCode::Home => b"\x1b[H",
+        KeyCode::End => b"\x1b[F",
+        KeyCode::Delete => b"\x1b[3~",
+        _ => return None,
+    };
+    Some(bytes.to_vec())
+}
+
+// some terminals report Ctrl-] as Ctrl-5
+fn is_quit(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char(']' | '5')) && key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<RawMode> {
+        terminal::enable_raw_mode()?;
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+pub fn attach(mut port: Box<dyn SerialPort>) -> io::Result<()> {
+    let mut reader = port.try_clone()?;
+    thread::spawn(move || {
+        let mut buf = [0; 256];
+        let mut stdout = io::stdout();
+        loop {
+            match reader.read(&mut buf) {
+                Ok(len) => {
+                    let _ = stdout.write_all(&buf[..len]);
+                    let _ = stdout.flush();
+                }
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => {}
+                Err(_) => break,
+            }
+        }
+    });
+
+    eprint!("[terminal] Attached, press Ctrl-] to quit\r\n");
+    let _raw = RawMode::enable()?;
+    loop {
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            if is_quit(&key) {
+                return Ok(());
+            }
+            if let Some(bytes) = encode_key(&key) {
+                port.write_all(&bytes)?;
+                port.flush()?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn encodes_keys() {
+        let plain = |code| encode_key(&key(code, KeyModifiers::NONE));
+        assert_eq!(plain(KeyCode::Char('a')), Some(b"a".to_vec()));
+        assert_eq!(plain(KeyCode::Char('é')), Some("é".as_bytes().to_vec()));
+        assert_eq!(plain(KeyCode::Enter), Some(b"\r".to_vec()));
+        assert_eq!(plain(KeyCode::Backspace), Some(b"\x7f".to_vec()));
+        assert_eq!(plain(KeyCode::Up), Some(b"\x1b[A".to_vec()));
+        assert_eq!(plain(KeyCode::Delete), Some(b"\x1b[3~".to_vec()));
+        assert_eq!(plain(KeyCode::F(1)), None);
+
+        let ctrl = |c| encode_key(&key(KeyCode::Char(c), KeyModifiers::CONTROL));
+        assert_eq!(ctrl('c'), Some(vec![3]));
+        assert_eq!(ctrl('C'), Some(vec![3]));
+        assert_eq!(ctrl('1'), None);
+    }
+
+    #[test]
+    fn quits() {
+        assert!(is_quit(&key(KeyCode::Char(']'), KeyModifiers::CONTROL)));
+        assert!(is_quit(&key(KeyCode::Char('5'), KeyModifiers::CONTROL)));
+        assert!(!is_quit(&key(KeyCode::Char(']'), KeyModifiers::NONE)));
+    }
+}
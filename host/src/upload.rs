@@ -0,0 +1,135 @@
+// Talks to `bin/loader.rs`: wait for its prompt, send the size, then the
+// program, paced so that the board's 128-byte receive FIFO never overflows.
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const FIFO_SIZE: usize = 128;
+
+// the current prompt, and the one of older loaders
+const PROMPTS: [&str; 2] = ["[loader] > ", "[loader] Program size (bytes): "];
+const TRANSFER: &str = "Transfer the data below";
+const LOADED: &str = "Program loaded!";
+const TIMED_OUT: &str = "Transfer timed out";
+
+#[derive(Debug, Clone, Copy)]
+pub struct Options {
+    pub baud_rate: u32,
+    // bytes sent at once, at most `FIFO_SIZE`
+    pub chunk_size: usize,
+    // an extra pause after each chunk
+    pub chunk_delay: Duration,
+    pub prompt_timeout: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            baud_rate: 115200,
+            chunk_size: FIFO_SIZE / 2,
+            chunk_delay: Duration::ZERO,
+            prompt_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Options {
+    // time on the wire, with a start and a stop bit
+    fn wire_time(&self, bytes: usize) -> Duration {
+        Duration::from_micros(bytes as u64 * 10 * 1_000_000 / self.baud_rate as u64)
+    }
+}
+
+fn timed_out(what: &str) -> io::Error {
+    io::Error::new(
+        ErrorKind::TimedOut,
+        format!("timed out waiting for {}", what),
+    )
+}
+
+// reads what is available, a read timeout counts as nothing
+fn read_some<S: Read>(port: &mut S, received: &mut Vec<u8>) -> io::Result<()> {
+    let mut buf = [0; 256];
+    match port.read(&mut buf) {
+        Ok(len) => received.extend_from_slice(&buf[..len]),
+        Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::WouldBlock) => {}
+        Err(e) if e.kind() == ErrorKind::Interrupted => {}
+        Err(e) => return Err(e),
+    }
+    Ok(())
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle.as_bytes())
+}
+
+// returns everything received, up to and including the first of `patterns`
+fn expect<S: Read>(
+    port: &mut S,
+    patterns: &[&str],
+    timeout: Duration,
+) -> io::Result<(usize, Vec<u8>)> {
+    let deadline = Instant::now() + timeout;
+    let mut received = Vec::new();
+    while Instant::now() < deadline {
+        read_some(port, &mut received)?;
+        if let Some(i) = patterns.iter().position(|p| contains(&received, p)) {
+            return Ok((i, received));
+        }
+    }
+    Err(timed_out(patterns[0]))
+}
+
+// pokes the loader with empty lines until it shows its prompt
+pub fn wait_for_prompt<S: Read + Write>(port: &mut S, timeout: Duration) -> io::Result<()> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        port.write_all(b"\r")?;
+        port.flush()?;
+        let wait = Duration::from_secs(1).min(deadline.saturating_duration_since(Instant::now()));
+        match expect(port, &PROMPTS, wait) {
+            Ok(_) => return Ok(()),
+            Err(e) if e.kind() == ErrorKind::TimedOut => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(timed_out("the loader prompt"))
+}
+
+// sends a program to a loader waiting at its prompt,
+// `progress` is called with the bytes sent so far, and the total
+pub fn upload<S: Read + Write>(
+    port: &mut S,
+    program: &[u8],
+    options: &Options,
+    mut progress: impl FnMut(usize, usize),
+) -> io::Result<()> {
+    assert!(options.chunk_size > 0 && options.chunk_size <= FIFO_SIZE);
+
+    port.write_all(format!("{}\r", program.len()).as_bytes())?;
+    port.flush()?;
+    expect(port, &[TRANSFER], options.prompt_timeout)?;
+
+    let mut sent = 0;
+    progress(sent, program.len());
+    for chunk in program.chunks(options.chunk_size) {
+        port.write_all(chunk)?;
+        port.flush()?;
+        // let the chunk leave the wire before sending more
+        thread::sleep(options.wire_time(chunk.len()) + options.chunk_delay);
+
+        sent += chunk.len();
+        progress(sent, program.len());
+    }
+
+    match expect(port, &[LOADED, TIMED_OUT], options.prompt_timeout)? {
+        (0, _) => Ok(()),
+        (_, received) => Err(io::Error::other(format!(
+            "the loader gave up: {}",
+            String::from_utf8_lossy(&received).trim()
+        ))),
+    }
+}
@@ -0,0 +1,147 @@
+// Uploads programs to `fake_loader` over a pseudo-terminal, like `bin/upload`
+// does to the board.
+
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use serialport::{SerialPort, TTYPort};
+
+use cpu_host::upload::{self, Options};
+use cpu_link::crc::crc16;
+
+// kills the loader when the test ends, passed or not
+struct Loader {
+    child: Child,
+    // what it reports on stderr, line by line
+    reports: Receiver<String>,
+}
+
+impl Loader {
+    fn start(args: &[&str]) -> (Loader, Box<dyn SerialPort>) {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_fake_loader"))
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("cannot start fake_loader");
+
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let path = line
+            .trim()
+            .strip_prefix("[fake] Listening on ")
+            .unwrap_or_else(|| panic!("unexpected output: {:?}", line))
+            .to_string();
+
+        let (tx, reports) = mpsc::channel();
+        let stderr = BufReader::new(child.stderr.take().unwrap());
+        thread::spawn(move || {
+            for line in stderr.lines().map_while(Result::ok) {
+                let _ = tx.send(line);
+            }
+        });
+
+        let port = serialport::new(&path, 115200)
+            .timeout(Duration::from_millis(20))
+            .open()
+            .unwrap();
+        (Loader { child, reports }, port)
+    }
+
+    // the next "[fake] Received ..." line
+    fn report(&self) -> String {
+        loop {
+            let line = self.reports.recv_timeout(Duration::from_secs(5)).unwrap();
+            if line.starts_with("[fake] Received") {
+                return line;
+            }
+        }
+    }
+}
+
+impl Drop for Loader {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn program(len: usize) -> Vec<u8> {
+    // zeros and CRs included, the transfer has to be binary-safe
+    (0..len).map(|i| (i * 7 % 251) as u8).collect()
+}
+
+fn options() -> Options {
+    Options {
+        prompt_timeout: Duration::from_secs(5),
+        ..Options::default()
+    }
+}
+
+#[test]
+fn uploads_a_program() {
+    let (loader, mut port) = Loader::start(&[]);
+    upload::wait_for_prompt(&mut port, Duration::from_secs(5)).unwrap();
+
+    let program = program(3000);
+    let mut progress = Vec::new();
+    upload::upload(&mut port, &program, &options(), |sent, total| {
+        progress.push((sent, total))
+    })
+    .unwrap();
+
+    assert_eq!(progress.first(), Some(&(0, 3000)));
+    assert_eq!(progress.last(), Some(&(3000, 3000)));
+    assert!(progress.windows(2).all(|w| w[0].0 < w[1].0));
+    assert_eq!(
+        loader.report(),
+        format!(
+            "[fake] Received 3000 bytes, CRC-16 {:#06x}, 0 overflows",
+            crc16(&program)
+        )
+    );
+}
+
+#[test]
+fn uploads_again_after_the_first_program() {
+    let (loader, mut port) = Loader::start(&[]);
+    for len in [1, 200] {
+        upload::wait_for_prompt(&mut port, Duration::from_secs(5)).unwrap();
+        upload::upload(&mut port, &program(len), &options(), |_, _| {}).unwrap();
+        assert!(loader
+            .report()
+            .starts_with(&format!("[fake] Received {} bytes", len)));
+    }
+}
+
+#[test]
+fn paces_a_slow_board() {
+    // 100 us a byte is slower than the wire, so chunks need the extra delay,
+    // with room for sleeps that overshoot
+    let (loader, mut port) = Loader::start(&["--byte-time", "100"]);
+    upload::wait_for_prompt(&mut port, Duration::from_secs(5)).unwrap();
+
+    let options = Options {
+        chunk_size: 16,
+        chunk_delay: Duration::from_millis(20),
+        ..options()
+    };
+    upload::upload(&mut port, &program(500), &options, |_, _| {}).unwrap();
+    let report = loader.report();
+    assert!(report.ends_with(", 0 overflows"), "{}", report);
+}
+
+#[test]
+fn no_loader() {
+    // a pty nobody answers on
+    let (mut port, _other) = TTYPort::pair().unwrap();
+    port.set_timeout(Duration::from_millis(20)).unwrap();
+
+    let error = upload::wait_for_prompt(&mut port, Duration::from_millis(300)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TimedOut);
+}
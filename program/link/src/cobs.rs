@@ -0,0 +1,127 @@
+// Consistent Overhead Byte Stuffing
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CobsError {
+    // a zero byte, or a block running past the end
+    Malformed,
+    OutputTooSmall,
+}
+
+// the worst case, without delimiters
+pub const fn max_encoded_len(len: usize) -> usize {
+    len + len / 254 + 1
+}
+
+// returns the encoded length, panics if `out` is shorter than `max_encoded_len`
+pub fn encode(data: &[u8], out: &mut [u8]) -> usize {
+    let mut code_idx = 0;
+    let mut out_idx = 1;
+    let mut code = 1u8;
+
+    for &b in data {
+        if b != 0 {
+            out[out_idx] = b;
+            out_idx += 1;
+            code += 1;
+        }
+        if b == 0 || code == 0xFF {
+            out[code_idx] = code;
+            code_idx = out_idx;
+            out_idx += 1;
+            code = 1;
+        }
+    }
+
+    out[code_idx] = code;
+    out_idx
+}
+
+// returns the decoded length
+pub fn decode(data: &[u8], out: &mut [u8]) -> Result<usize, CobsError> {
+    let mut i = 0;
+    let mut len = 0;
+
+    while i < data.len() {
+        let code = data[i] as usize;
+        let end = i + code;
+        if code == 0 || end > data.len() {
+            return Err(CobsError::Malformed);
+        }
+
+        for &b in &data[i + 1..end] {
+            if b == 0 {
+                return Err(CobsError::Malformed);
+            }
+            *out.get_mut(len).ok_or(CobsError::OutputTooSmall)? = b;
+            len += 1;
+        }
+        i = end;
+
+        // a zero follows every block, except full ones and the last one
+        if code != 0xFF && i < data.len() {
+            *out.get_mut(len).ok_or(CobsError::OutputTooSmall)? = 0;
+            len += 1;
+        }
+    }
+
+    Ok(len)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec;
+    use std::vec::Vec;
+
+    fn round_trip(data: &[u8]) -> Vec<u8> {
+        let mut encoded = vec![0; max_encoded_len(data.len())];
+        let len = encode(data, &mut encoded);
+        assert!(!encoded[..len].contains(&0));
+
+        let mut decoded = vec![0; data.len()];
+        let decoded_len = decode(&encoded[..len], &mut decoded).unwrap();
+        assert_eq!(&decoded[..decoded_len], data);
+        encoded.truncate(len);
+        encoded
+    }
+
+    #[test]
+    fn known_encodings() {
+        assert_eq!(round_trip(&[]), [1]);
+        assert_eq!(round_trip(&[0]), [1, 1]);
+        assert_eq!(round_trip(&[0, 0]), [1, 1, 1]);
+        assert_eq!(round_trip(&[0x11, 0x22, 0, 0x33]), [3, 0x11, 0x22, 2, 0x33]);
+        assert_eq!(round_trip(&[0x11, 0, 0]), [2, 0x11, 1, 1]);
+    }
+
+    #[test]
+    fn long_runs() {
+        for len in [253, 254, 255, 508, 600] {
+            let data: Vec<u8> = (0..len).map(|i| (i % 255 + 1) as u8).collect();
+            let encoded = round_trip(&data);
+            assert!(encoded.len() <= max_encoded_len(len));
+            assert_eq!(encoded[0], if len < 254 { len as u8 + 1 } else { 0xFF });
+        }
+
+        let mut data = vec![7; 300];
+        data[254] = 0;
+        data[299] = 0;
+        round_trip(&data);
+    }
+
+    #[test]
+    fn malformed() {
+        let mut out = [0; 16];
+        // a zero inside a block
+        assert_eq!(decode(&[3, 1, 0], &mut out), Err(CobsError::Malformed));
+        // a block running past the end
+        assert_eq!(decode(&[4, 1, 2], &mut out), Err(CobsError::Malformed));
+        assert_eq!(decode(&[0], &mut out), Err(CobsError::Malformed));
+        assert_eq!(
+            decode(&[3, 1, 2, 1], &mut out[..2]),
+            Err(CobsError::OutputTooSmall)
+        );
+    }
+}
@@ -0,0 +1,42 @@
+// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xFFFF, no reflection.
+// Bitwise rather than table-driven, to keep the loader small.
+
+pub const INIT: u16 = 0xFFFF;
+
+pub fn update(crc: u16, byte: u8) -> u16 {
+    let mut crc = crc ^ (byte as u16) << 8;
+    for _ in 0..8 {
+        crc = if crc & 0x8000 != 0 {
+            crc << 1 ^ 0x1021
+        } else {
+            crc << 1
+        };
+    }
+    crc
+}
+
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(INIT, |crc, b| update(crc, *b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+        assert_eq!(crc16(&[]), INIT);
+    }
+
+    #[test]
+    fn catches_flipped_bits() {
+        let data = *b"stop-and-wait";
+        let crc = crc16(&data);
+        for i in 0..data.len() * 8 {
+            let mut broken = data;
+            broken[i / 8] ^= 1 << (i % 8);
+            assert_ne!(crc16(&broken), crc);
+        }
+    }
+}
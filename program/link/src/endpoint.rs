@@ -0,0 +1,484 @@
+use core::time::Duration;
+
+use crate::frame::{Decoder, Frame, FrameKind, MAX_ENCODED, MAX_PAYLOAD};
+
+// a byte stream, and a clock to time the retransmissions
+pub trait Transport {
+    type Error;
+
+    // returns `None` right away if nothing has arrived
+    fn read(&mut self) -> Result<Option<u8>, Self::Error>;
+
+    fn write(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+
+    // time since an arbitrary point, never going backwards
+    fn now(&mut self) -> Duration;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    Transport(E),
+    // the payload is longer than `MAX_PAYLOAD`
+    TooLong,
+    // `recv` got nothing in time
+    Timeout,
+    // the peer did not acknowledge after all the retries
+    NoAck,
+    // the received message (of this length) does not fit, it is kept for the next `recv`
+    BufferTooSmall(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    // how long to wait for an ACK before sending again
+    pub ack_timeout: Duration,
+    pub retries: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        // a full frame takes about 22 ms at 115200 baud
+        Config {
+            ack_timeout: Duration::from_millis(200),
+            retries: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    pub sent: u32,
+    pub retransmits: u32,
+    pub received: u32,
+    pub duplicates: u32,
+    // Reset frames from the peer, resent ones included
+    pub resets: u32,
+    // broken frames, e.g. a wrong CRC
+    pub bad_frames: u32,
+}
+
+enum Event {
+    Data,
+    Ack(u8),
+    Nak,
+}
+
+// One end of the link, with a stop-and-wait sender and a receiver holding
+// one message. Each data frame carries a sequence number, so that a frame
+// sent again because its ACK was lost is acknowledged but not delivered twice.
+// The first `send` starts a session with a Reset frame.
+pub struct Endpoint<T> {
+    transport: T,
+    config: Config,
+    stats: Stats,
+    tx_seq: u8,
+    // whether the peer has acknowledged our Reset
+    synced: bool,
+    last_rx_seq: Option<u8>,
+    decoder: Decoder,
+    pending: [u8; MAX_PAYLOAD],
+    pending_len: Option<usize>,
+}
+
+impl<T: Transport> Endpoint<T> {
+    pub fn new(transport: T) -> Endpoint<T> {
+        Endpoint::with_config(transport, Config::default())
+    }
+
+    pub fn with_config(transport: T, config: Config) -> Endpoint<T> {
+        Endpoint {
+            transport,
+            config,
+            stats: Stats::default(),
+            tx_seq: 0,
+            synced: false,
+            last_rx_seq: None,
+            decoder: Decoder::new(),
+            pending: [0; MAX_PAYLOAD],
+            pending_len: None,
+        }
+    }
+
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    pub fn transport(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    // blocks until the peer acknowledges `payload`
+    pub fn send(&mut self, payload: &[u8]) -> Result<(), Error<T::Error>> {
+        if payload.len() > MAX_PAYLOAD {
+            return Err(Error::TooLong);
+        }
+
+        if !self.synced {
+            self.transmit(Frame::control(FrameKind::Reset, self.tx_seq))?;
+            self.synced = true;
+        }
+        self.transmit(Frame::data(self.tx_seq, payload))?;
+        self.stats.sent += 1;
+        Ok(())
+    }
+
+    // sends `frame` until it is acknowledged, then moves on to the next sequence number
+    fn transmit(&mut self, frame: Frame) -> Result<(), Error<T::Error>> {
+        let mut encoded = [0; MAX_ENCODED];
+        let len = frame.encode(&mut encoded);
+
+        for attempt in 0..=self.config.retries {
+            if attempt > 0 {
+                self.stats.retransmits += 1;
+            }
+            self.transport
+                .write(&encoded[..len])
+                .map_err(Error::Transport)?;
+
+            let deadline = self.transport.now() + self.config.ack_timeout;
+            while self.transport.now() < deadline {
+                match self.process()? {
+                    Some(Event::Ack(acked)) if acked == frame.seq => {
+                        self.tx_seq = frame.seq.wrapping_add(1);
+                        return Ok(());
+                    }
+                    Some(Event::Nak) => break,
+                    _ => {}
+                }
+            }
+        }
+
+        Err(Error::NoAck)
+    }
+
+    // blocks until a message arrives, and returns its length
+    pub fn recv(&mut self, buf: &mut [u8], timeout: Duration) -> Result<usize, Error<T::Error>> {
+        let deadline = self.transport.now() + timeout;
+        loop {
+            if let Some(len) = self.try_recv(buf)? {
+                return Ok(len);
+            }
+            if self.transport.now() >= deadline {
+                return Err(Error::Timeout);
+            }
+        }
+    }
+
+    // handles whatever has arrived, and returns the length of a message if there is one
+    pub fn try_recv(&mut self, buf: &mut [u8]) -> Result<Option<usize>, Error<T::Error>> {
+        while self.pending_len.is_none() {
+            if self.process()?.is_none() {
+                return Ok(None);
+            }
+        }
+
+        let len = self.pending_len.unwrap();
+        if buf.len() < len {
+            return Err(Error::BufferTooSmall(len));
+        }
+        buf[..len].copy_from_slice(&self.pending[..len]);
+        self.pending_len = None;
+        Ok(Some(len))
+    }
+
+    // reads until a frame is handled, or nothing is left
+    fn process(&mut self) -> Result<Option<Event>, Error<T::Error>> {
+        while let Some(byte) = self.transport.read().map_err(Error::Transport)? {
+            let (kind, seq) = match self.decoder.push(byte) {
+                None => continue,
+                Some(Ok(frame)) => {
+                    if frame.kind == FrameKind::Data && self.last_rx_seq != Some(frame.seq) {
+                        if self.pending_len.is_some() {
+                            // no room yet, the peer will send it again
+                            continue;
+                        }
+                        self.pending[..frame.payload.len()].copy_from_slice(frame.payload);
+                        self.pending_len = Some(frame.payload.len());
+                    }
+                    (frame.kind, frame.seq)
+                }
+                Some(Err(_)) => {
+                    self.stats.bad_frames += 1;
+                    let expected = self.last_rx_seq.map_or(0, |s| s.wrapping_add(1));
+                    send_control(&mut self.transport, FrameKind::Nak, expected)?;
+                    continue;
+                }
+            };
+
+            match kind {
+                FrameKind::Data if self.last_rx_seq == Some(seq) => {
+                    // our ACK was lost
+                    self.stats.duplicates += 1;
+                    send_control(&mut self.transport, FrameKind::Ack, seq)?;
+                }
+                FrameKind::Data => {
+                    self.last_rx_seq = Some(seq);
+                    self.stats.received += 1;
+                    send_control(&mut self.transport, FrameKind::Ack, seq)?;
+                    return Ok(Some(Event::Data));
+                }
+                FrameKind::Reset => {
+                    // sent again if our ACK was lost, nothing was received in between
+                    self.stats.resets += 1;
+                    self.last_rx_seq = None;
+                    send_control(&mut self.transport, FrameKind::Ack, seq)?;
+                }
+                FrameKind::Ack => return Ok(Some(Event::Ack(seq))),
+                FrameKind::Nak => return Ok(Some(Event::Nak)),
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn send_control<T: Transport>(
+    transport: &mut T,
+    kind: FrameKind,
+    seq: u8,
+) -> Result<(), Error<T::Error>> {
+    let mut encoded = [0; MAX_ENCODED];
+    let len = Frame::control(kind, seq).encode(&mut encoded);
+    transport.write(&encoded[..len]).map_err(Error::Transport)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use core::convert::Infallible;
+    use std::boxed::Box;
+    use std::collections::VecDeque;
+    use std::vec::Vec;
+
+    // the other end, answering each frame written to it with an optional ACK or NAK
+    struct Mock {
+        input: VecDeque<u8>,
+        written: Vec<(FrameKind, u8, Vec<u8>)>,
+        decoder: Decoder,
+        clock: Duration,
+        answer: Box<dyn FnMut(FrameKind, u8) -> Option<FrameKind>>,
+    }
+
+    impl Mock {
+        fn new(answer: impl FnMut(FrameKind, u8) -> Option<FrameKind> + 'static) -> Mock {
+            Mock {
+                input: VecDeque::new(),
+                written: Vec::new(),
+                decoder: Decoder::new(),
+                clock: Duration::ZERO,
+                answer: Box::new(answer),
+            }
+        }
+
+        fn feed(&mut self, frame: Frame) {
+            let mut encoded = [0; MAX_ENCODED];
+            let len = frame.encode(&mut encoded);
+            self.input.extend(&encoded[..len]);
+        }
+
+        fn kinds(&self) -> Vec<(FrameKind, u8)> {
+            self.written.iter().map(|(k, s, _)| (*k, *s)).collect()
+        }
+    }
+
+    impl Transport for Mock {
+        type Error = Infallible;
+
+        fn read(&mut self) -> Result<Option<u8>, Infallible> {
+            Ok(self.input.pop_front())
+        }
+
+        fn write(&mut self, data: &[u8]) -> Result<(), Infallible> {
+            for &byte in data {
+                if let Some(Ok(frame)) = self.decoder.push(byte) {
+                    let (kind, seq) = (frame.kind, frame.seq);
+                    self.written.push((kind, seq, frame.payload.to_vec()));
+                    if let Some(reply) = (self.answer)(kind, seq) {
+                        self.feed(Frame::control(reply, seq));
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        fn now(&mut self) -> Duration {
+            self.clock += Duration::from_millis(1);
+            self.clock
+        }
+    }
+
+    fn ack_all(_: FrameKind, _: u8) -> Option<FrameKind> {
+        Some(FrameKind::Ack)
+    }
+
+    #[test]
+    fn send_starts_a_session() {
+        let mut link = Endpoint::new(Mock::new(ack_all));
+        link.send(b"one").unwrap();
+        link.send(b"two").unwrap();
+
+        let mock = link.transport();
+        assert_eq!(
+            mock.kinds(),
+            [
+                (FrameKind::Reset, 0),
+                (FrameKind::Data, 1),
+                (FrameKind::Data, 2)
+            ]
+        );
+        assert_eq!(mock.written[2].2, b"two");
+        assert_eq!(link.stats().sent, 2);
+        assert_eq!(link.stats().retransmits, 0);
+    }
+
+    #[test]
+    fn retransmits_until_acknowledged() {
+        let mut lost = 0;
+        let mut link = Endpoint::new(Mock::new(move |kind, _| {
+            if kind == FrameKind::Data && lost < 2 {
+                lost += 1;
+                return None;
+            }
+            Some(FrameKind::Ack)
+        }));
+        link.send(b"x").unwrap();
+
+        let data = link
+            .transport()
+            .kinds()
+            .iter()
+            .filter(|(k, _)| *k == FrameKind::Data)
+            .count();
+        assert_eq!(data, 3);
+        assert_eq!(link.stats().retransmits, 2);
+        // each attempt waited out the ACK timeout
+        assert!(link.transport().clock >= 2 * Config::default().ack_timeout);
+    }
+
+    #[test]
+    fn nak_resends_right_away() {
+        let mut naks = 0;
+        let mut link = Endpoint::new(Mock::new(move |kind, _| {
+            if kind == FrameKind::Data && naks == 0 {
+                naks += 1;
+                return Some(FrameKind::Nak);
+            }
+            Some(FrameKind::Ack)
+        }));
+        link.send(b"x").unwrap();
+
+        assert_eq!(link.stats().retransmits, 1);
+        assert!(link.transport().clock < Config::default().ack_timeout);
+    }
+
+    #[test]
+    fn ignores_stale_acks() {
+        let config = Config {
+            ack_timeout: Duration::from_millis(10),
+            retries: 1,
+        };
+        let mut link = Endpoint::with_config(Mock::new(|_, _| None), config);
+        // left over from an earlier frame
+        link.transport().feed(Frame::control(FrameKind::Ack, 5));
+        assert_eq!(link.send(b"x"), Err(Error::NoAck));
+    }
+
+    #[test]
+    fn timeouts() {
+        let config = Config {
+            ack_timeout: Duration::from_millis(10),
+            retries: 3,
+        };
+        let mut link = Endpoint::with_config(Mock::new(|_, _| None), config);
+        assert_eq!(link.send(b"x"), Err(Error::NoAck));
+        assert_eq!(link.transport().written.len(), 4);
+        assert_eq!(link.stats().sent, 0);
+
+        let mut buf = [0; MAX_PAYLOAD];
+        let start = link.transport().clock;
+        assert_eq!(
+            link.recv(&mut buf, Duration::from_millis(50)),
+            Err(Error::Timeout)
+        );
+        assert!(link.transport().clock - start >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn duplicates_are_acknowledged_once_delivered() {
+        let mut link = Endpoint::new(Mock::new(|_, _| None));
+        let mock = link.transport();
+        mock.feed(Frame::data(0, b"a"));
+        mock.feed(Frame::data(0, b"a"));
+        mock.feed(Frame::data(1, b"b"));
+
+        let mut buf = [0; MAX_PAYLOAD];
+        assert_eq!(link.try_recv(&mut buf), Ok(Some(1)));
+        assert_eq!(&buf[..1], b"a");
+        assert_eq!(link.try_recv(&mut buf), Ok(Some(1)));
+        assert_eq!(&buf[..1], b"b");
+        assert_eq!(link.try_recv(&mut buf), Ok(None));
+
+        assert_eq!(
+            link.transport().kinds(),
+            [
+                (FrameKind::Ack, 0),
+                (FrameKind::Ack, 0),
+                (FrameKind::Ack, 1)
+            ]
+        );
+        assert_eq!(link.stats().received, 2);
+        assert_eq!(link.stats().duplicates, 1);
+    }
+
+    #[test]
+    fn reset_forgets_the_sequence() {
+        let mut link = Endpoint::new(Mock::new(|_, _| None));
+        let mock = link.transport();
+        mock.feed(Frame::data(0, b"a"));
+        // the peer restarted, and counts from 0 again
+        mock.feed(Frame::control(FrameKind::Reset, 0));
+        mock.feed(Frame::control(FrameKind::Reset, 0));
+        mock.feed(Frame::data(0, b"b"));
+
+        let mut buf = [0; MAX_PAYLOAD];
+        assert_eq!(link.try_recv(&mut buf), Ok(Some(1)));
+        assert_eq!(link.try_recv(&mut buf), Ok(Some(1)));
+        assert_eq!(&buf[..1], b"b");
+        assert_eq!(link.stats().received, 2);
+        assert_eq!(link.stats().resets, 2);
+        assert_eq!(link.stats().duplicates, 0);
+    }
+
+    #[test]
+    fn bad_frames_are_naked() {
+        let mut link = Endpoint::new(Mock::new(|_, _| None));
+        let mock = link.transport();
+        mock.feed(Frame::data(0, b"a"));
+        let mut encoded = [0; MAX_ENCODED];
+        let len = Frame::data(1, b"b").encode(&mut encoded);
+        encoded[3] ^= 0x40;
+        mock.input.extend(&encoded[..len]);
+
+        let mut buf = [0; MAX_PAYLOAD];
+        assert_eq!(link.try_recv(&mut buf), Ok(Some(1)));
+        assert_eq!(link.try_recv(&mut buf), Ok(None));
+        assert_eq!(
+            link.transport().kinds(),
+            [(FrameKind::Ack, 0), (FrameKind::Nak, 1)]
+        );
+        assert_eq!(link.stats().bad_frames, 1);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::cobs::{self, CobsError};
+use crate::crc::crc16;
+
+pub const DELIMITER: u8 = 0;
+
+pub const MAX_PAYLOAD: usize = 240;
+
+const HEADER_LEN: usize = 2;
+const CRC_LEN: usize = 2;
+
+// a decoded frame, with the CRC
+pub const MAX_FRAME: usize = HEADER_LEN + MAX_PAYLOAD + CRC_LEN;
+
+// an encoded frame, with both delimiters
+pub const MAX_ENCODED: usize = cobs::max_encoded_len(MAX_FRAME) + 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FrameKind {
+    Data = 1,
+    Ack = 2,
+    Nak = 3,
+    // the sender starts a new session, forget its sequence numbers
+    Reset = 4,
+}
+
+impl TryFrom<u8> for FrameKind {
+    type Error = DecodeError;
+
+    fn try_from(kind: u8) -> Result<Self, Self::Error> {
+        match kind {
+            1 => Ok(FrameKind::Data),
+            2 => Ok(FrameKind::Ack),
+            3 => Ok(FrameKind::Nak),
+            4 => Ok(FrameKind::Reset),
+            kind => Err(DecodeError::UnknownKind(kind)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    pub kind: FrameKind,
+    pub seq: u8,
+    pub payload: &'a [u8],
+}
+
+impl<'a> Frame<'a> {
+    pub fn data(seq: u8, payload: &'a [u8]) -> Frame<'a> {
+        Frame {
+            kind: FrameKind::Data,
+            seq,
+            payload,
+        }
+    }
+
+    pub fn control(kind: FrameKind, seq: u8) -> Frame<'static> {
+        Frame {
+            kind,
+            seq,
+            payload: &[],
+        }
+    }
+
+    // returns the length written to `out`, panics if the payload is longer than `MAX_PAYLOAD`
+    pub fn encode(&self, out: &mut [u8; MAX_ENCODED]) -> usize {
+        assert!(self.payload.len() <= MAX_PAYLOAD);
+
+        let len = HEADER_LEN + self.payload.len();
+        let mut raw = [0; MAX_FRAME];
+        raw[0] = self.kind as u8;
+        raw[1] = self.seq;
+        raw[HEADER_LEN..len].copy_from_slice(self.payload);
+        let crc = crc16(&raw[..len]);
+        raw[len..len + CRC_LEN].copy_from_slice(&crc.to_be_bytes());
+
+        out[0] = DELIMITER;
+        let encoded = cobs::encode(&raw[..len + CRC_LEN], &mut out[1..]);
+        out[encoded + 1] = DELIMITER;
+        encoded + 2
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    Cobs(CobsError),
+    TooLong,
+    TooShort,
+    Crc,
+    UnknownKind(u8),
+}
+
+// splits a byte stream into frames
+pub struct Decoder {
+    encoded: [u8; MAX_ENCODED],
+    len: usize,
+    overflow: bool,
+    frame: [u8; MAX_FRAME],
+}
+
+impl Decoder {
+    pub const fn new() -> Decoder {
+        Decoder {
+            encoded: [0; MAX_ENCODED],
+            len: 0,
+            overflow: false,
+            frame: [0; MAX_FRAME],
+        }
+    }
+
+    // drops a partially received frame
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.overflow = false;
+    }
+
+    // returns a frame (or why it is broken) once its closing delimiter arrives
+    pub fn push(&mut self, byte: u8) -> Option<Result<Frame<'_>, DecodeError>> {
+        if byte != DELIMITER {
+            if self.len < self.encoded.len() {
+                self.encoded[self.len] = byte;
+                self.len += 1;
+            } else {
+                self.overflow = true;
+            }
+            return None;
+        }
+
+        let (len, overflow) = (self.len, self.overflow);
+        self.reset();
+        match (len, overflow) {
+            // back-to-back delimiters
+            (0, false) => None,
+            (_, true) => Some(Err(DecodeError::TooLong)),
+            (len, false) => Some(self.decode(len)),
+        }
+    }
+
+    fn decode(&mut self, len: usize) -> Result<Frame<'_>, DecodeError> {
+        let len = cobs::decode(&self.encoded[..len], &mut self.frame).map_err(|e| match e {
+            CobsError::OutputTooSmall => DecodeError::TooLong,
+            e => DecodeError::Cobs(e),
+        })?;
+        if len < HEADER_LEN + CRC_LEN {
+            return Err(DecodeError::TooShort);
+        }
+
+        let (body, crc) = self.frame[..len].split_at(len - CRC_LEN);
+        if crc16(body) != u16::from_be_bytes([crc[0], crc[1]]) {
+            return Err(DecodeError::Crc);
+        }
+
+        Ok(Frame {
+            kind: FrameKind::try_from(body[0])?,
+            seq: body[1],
+            payload: &body[HEADER_LEN..],
+        })
+    }
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::vec::Vec;
+
+    fn encode(frame: Frame) -> Vec<u8> {
+        let mut out = [0; MAX_ENCODED];
+        let len = frame.encode(&mut out);
+        out[..len].to_vec()
+    }
+
+    type Decoded = Result<(FrameKind, u8, Vec<u8>), DecodeError>;
+
+    fn decode_all(bytes: &[u8]) -> Vec<Decoded> {
+        let mut decoder = Decoder::new();
+        bytes
+            .iter()
+            .filter_map(|b| {
+                let result = decoder.push(*b)?;
+                Some(result.map(|f| (f.kind, f.seq, f.payload.to_vec())))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let payload: Vec<u8> = (0..MAX_PAYLOAD).map(|i| i as u8).collect();
+        for (kind, seq, payload) in [
+            (FrameKind::Data, 0, &b"hello"[..]),
+            (FrameKind::Data, 255, &payload[..]),
+            (FrameKind::Data, 1, &[0, 0, 0][..]),
+            (FrameKind::Ack, 7, &[][..]),
+            (FrameKind::Reset, 0, &[][..]),
+        ] {
+            let encoded = encode(Frame { kind, seq, payload });
+            assert!(encoded.len() <= MAX_ENCODED);
+            assert_eq!(encoded.iter().filter(|b| **b == DELIMITER).count(), 2);
+            assert_eq!(decode_all(&encoded), [Ok((kind, seq, payload.to_vec()))]);
+        }
+    }
+
+    #[test]
+    fn corruption() {
+        let encoded = encode(Frame::data(3, b"some payload"));
+        for i in 1..encoded.len() - 1 {
+            let mut broken = encoded.clone();
+            broken[i] ^= 0x40;
+            let result = decode_all(&broken);
+            assert_eq!(result.len(), 1);
+            assert!(result[0].is_err(), "byte {} flipped", i);
+        }
+
+        // a lost byte
+        let mut short = encoded.clone();
+        short.remove(5);
+        assert!(decode_all(&short)[0].is_err());
+
+        // only the kind arrived
+        assert_eq!(decode_all(&[0, 2, 1, 0]), [Err(DecodeError::TooShort)]);
+    }
+
+    #[test]
+    fn resynchronizes() {
+        let mut bytes = b"\x05garbage".to_vec();
+        bytes.extend(encode(Frame::data(1, b"a")));
+        bytes.extend([0x42; 3 * MAX_ENCODED]);
+        bytes.push(DELIMITER);
+        bytes.extend(encode(Frame::control(FrameKind::Ack, 1)));
+
+        let frames = decode_all(&bytes);
+        assert_eq!(frames.len(), 4);
+        assert!(frames[0].is_err());
+        assert_eq!(frames[1], Ok((FrameKind::Data, 1, b"a".to_vec())));
+        assert_eq!(frames[2], Err(DecodeError::TooLong));
+        assert_eq!(frames[3], Ok((FrameKind::Ack, 1, Vec::new())));
+    }
+
+    #[test]
+    fn unknown_kind() {
+        let mut raw = [9, 0, 0, 0];
+        let crc = crc16(&raw[..2]);
+        raw[2..].copy_from_slice(&crc.to_be_bytes());
+        let mut bytes = [0; 8];
+        let len = cobs::encode(&raw, &mut bytes[1..]);
+        assert_eq!(
+            decode_all(&bytes[..len + 2]),
+            [Err(DecodeError::UnknownKind(9))]
+        );
+    }
+}
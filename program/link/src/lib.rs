@@ -0,0 +1,22 @@
+#![no_std]
+
+// Frames on the wire:
+//
+//     0x00, COBS(kind, seq, payload..., crc16 high, crc16 low), 0x00
+//
+// COBS removes every zero byte from the frame, so a zero always marks a frame
+// boundary, and the receiver resynchronizes after any garbage. The CRC covers
+// kind, seq and payload. Data frames are retransmitted until acknowledged.
+//
+// A new endpoint sends a Reset frame, acknowledged like data, before its
+// first data frame. The peer then forgets the last sequence number it got,
+// so that a restarted program starting again at 0 is not taken for a
+// duplicate.
+
+pub mod cobs;
+pub mod crc;
+pub mod endpoint;
+pub mod frame;
+
+pub use endpoint::{Config, Endpoint, Error, Stats, Transport};
+pub use frame::{DecodeError, Decoder, Frame, FrameKind, MAX_ENCODED, MAX_PAYLOAD};
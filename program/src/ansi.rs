@@ -0,0 +1,313 @@
+// Splits output into printable bytes, control characters and the escape
+// sequences a VT100 understands. What they do is up to the user, see
+// `monitor::monitor::putchar`.
+
+use crate::monitor::Color;
+
+pub const MAX_PARAMS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Print(u8),
+    // a C0 control character, like `\n`
+    Control(u8),
+    // `ESC x`, e.g. `ESC 7`
+    Escape(u8),
+    // `ESC [ params x`
+    Csi(Csi),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Csi {
+    params: [u16; MAX_PARAMS],
+    len: usize,
+    // `ESC [ ?`, the DEC private modes
+    pub private: bool,
+    pub command: u8,
+}
+
+impl Csi {
+    const fn new() -> Csi {
+        Csi {
+            params: [0; MAX_PARAMS],
+            len: 0,
+            private: false,
+            command: 0,
+        }
+    }
+
+    // an empty parameter is 0, extra ones are dropped
+    pub fn params(&self) -> &[u16] {
+        &self.params[..self.len]
+    }
+
+    // a missing or zero parameter means `default`
+    pub fn param(&self, index: usize, default: u16) -> u16 {
+        match self.params().get(index) {
+            Some(0) | None => default,
+            Some(value) => *value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Ground,
+    Escape,
+    // `ESC (`, `ESC #` and the like, their final byte is dropped
+    EscapeIntermediate,
+    Csi,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Parser {
+    state: State,
+    csi: Csi,
+}
+
+impl Parser {
+    pub const fn new() -> Parser {
+        Parser {
+            state: State::Ground,
+            csi: Csi::new(),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.state = State::Ground;
+    }
+
+    pub fn push(&mut self, byte: u8) -> Option<Action> {
+        // CAN and SUB cancel a sequence, ESC starts over
+        match byte {
+            b'\x18' | b'\x1a' => {
+                self.state = State::Ground;
+                return None;
+            }
+            b'\x1b' => {
+                self.state = State::Escape;
+                return None;
+            }
+            // DEL is a filler
+            b'\x7f' => return None,
+            _ => {}
+        }
+
+        match self.state {
+            // other control characters still work in the middle of a sequence
+            _ if byte < 0x20 => Some(Action::Control(byte)),
+            State::Ground => Some(Action::Print(byte)),
+            State::Escape => match byte {
+                b'[' => {
+                    self.csi = Csi::new();
+                    self.state = State::Csi;
+                    None
+                }
+                0x20..=0x2F => {
+                    self.state = State::EscapeIntermediate;
+                    None
+                }
+                _ => {
+                    self.state = State::Ground;
+                    Some(Action::Escape(byte))
+                }
+            },
+            State::EscapeIntermediate => {
+                if byte >= 0x30 {
+                    self.state = State::Ground;
+                }
+                None
+            }
+            State::Csi => self.csi(byte),
+        }
+    }
+
+    fn csi(&mut self, byte: u8) -> Option<Action> {
+        let csi = &mut self.csi;
+        match byte {
+            b'0'..=b'9' => {
+                if csi.len == 0 {
+                    csi.len = 1;
+                }
+                if let Some(param) = csi.params.get_mut(csi.len - 1) {
+                    *param = param
+                        .saturating_mul(10)
+                        .saturating_add((byte - b'0') as u16);
+                }
+            }
+            b';' => {
+                // `ESC [ ; 5 H` has an empty first parameter
+                csi.len = (csi.len.max(1) + 1).min(MAX_PARAMS + 1);
+            }
+            b'?' => csi.private = true,
+            // other parameter and intermediate bytes
+            0x20..=0x3F => {}
+            _ => {
+                csi.len = csi.len.min(MAX_PARAMS);
+                csi.command = byte;
+                self.state = State::Ground;
+                return Some(Action::Csi(*csi));
+            }
+        }
+        None
+    }
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// what SGR (`ESC [ ... m`) sets, the ANSI color numbers match `Color`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attributes {
+    // background << 4 | foreground
+    pub color: u8,
+    // shown as the bright foreground
+    pub bold: bool,
+}
+
+impl Attributes {
+    pub const DEFAULT: Attributes = Attributes {
+        color: (Color::Black as u8) << 4 | Color::White as u8,
+        bold: false,
+    };
+
+    pub fn apply(&mut self, params: &[u16]) {
+        // `ESC [ m` resets too
+        if params.is_empty() {
+            *self = Attributes::DEFAULT;
+            return;
+        }
+
+        let default = Attributes::DEFAULT.color;
+        let (mut fg, mut bg) = (self.color & 0xF, self.color >> 4);
+        for param in params {
+            match *param {
+                0 => {
+                    (fg, bg) = (default & 0xF, default >> 4);
+                    self.bold = false;
+                }
+                1 => self.bold = true,
+                22 => {
+                    self.bold = false;
+                    fg &= 0x7;
+                }
+                p @ 30..=37 => fg = (p - 30) as u8,
+                39 => fg = default & 0xF,
+                p @ 40..=47 => bg = (p - 40) as u8,
+                49 => bg = default >> 4,
+                p @ 90..=97 => fg = (p - 90) as u8 | 0x8,
+                p @ 100..=107 => bg = (p - 100) as u8 | 0x8,
+                _ => {}
+            }
+        }
+        if self.bold {
+            fg |= 0x8;
+        }
+        self.color = bg << 4 | fg;
+    }
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Attributes::DEFAULT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the last action of `bytes`
+    fn parse(bytes: &[u8]) -> Option<Action> {
+        let mut parser = Parser::new();
+        bytes.iter().fold(None, |_, b| parser.push(*b))
+    }
+
+    fn csi(bytes: &[u8]) -> Csi {
+        match parse(bytes) {
+            Some(Action::Csi(csi)) => csi,
+            other => panic!("{:?} for {:?}", other, bytes),
+        }
+    }
+
+    #[test]
+    fn csi_params() {
+        let c = csi(b"\x1b[H");
+        assert_eq!((c.params(), c.command), (&[][..], b'H'));
+        assert_eq!((c.param(0, 1), c.param(1, 1)), (1, 1));
+
+        let c = csi(b"\x1b[12;34H");
+        assert_eq!(c.params(), [12, 34]);
+
+        // empty and zero parameters take the default
+        let c = csi(b"\x1b[;5H");
+        assert_eq!(c.params(), [0, 5]);
+        assert_eq!((c.param(0, 1), c.param(1, 1)), (1, 5));
+        assert_eq!(csi(b"\x1b[0A").param(0, 1), 1);
+        assert_eq!(csi(b"\x1b[5;m").params(), [5, 0]);
+
+        assert_eq!(csi(b"\x1b[99999C").params(), [u16::MAX]);
+        let c = csi(b"\x1b[1;2;3;4;5;6;7;8;9;10m");
+        assert_eq!(c.params(), [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let c = csi(b"\x1b[?25l");
+        assert!(c.private);
+        assert_eq!((c.params(), c.command), (&[25][..], b'l'));
+        // the parameters of one sequence do not leak into the next
+        let mut parser = Parser::new();
+        b"\x1b[?7;8h\x1b[2".iter().for_each(|b| {
+            parser.push(*b);
+        });
+        assert_eq!(parser.push(b'J'), Some(Action::Csi(csi(b"\x1b[2J"))));
+    }
+
+    #[test]
+    fn controls_and_escapes() {
+        assert_eq!(parse(b"a"), Some(Action::Print(b'a')));
+        assert_eq!(parse(b"\xDB"), Some(Action::Print(0xDB)));
+        assert_eq!(parse(b"\n"), Some(Action::Control(b'\n')));
+        assert_eq!(parse(b"\x1b7"), Some(Action::Escape(b'7')));
+        // a designated character set is dropped, and what follows printed
+        assert_eq!(parse(b"\x1b(B"), None);
+        assert_eq!(parse(b"\x1b(Bx"), Some(Action::Print(b'x')));
+        // a control character in the middle of a sequence
+        let mut parser = Parser::new();
+        assert_eq!(parser.push(0x1b), None);
+        assert_eq!(parser.push(b'['), None);
+        assert_eq!(parser.push(b'\r'), Some(Action::Control(b'\r')));
+        assert_eq!(parser.push(b'2'), None);
+        assert!(matches!(parser.push(b'K'), Some(Action::Csi(_))));
+        // cancelled, or started over
+        assert_eq!(parse(b"\x1b[3\x18m"), Some(Action::Print(b'm')));
+        assert_eq!(csi(b"\x1b[3\x1b[4m").params(), [4]);
+        assert_eq!(parse(b"\x7f"), None);
+    }
+
+    #[test]
+    fn sgr() {
+        let apply = |params: &[u16]| {
+            let mut attributes = Attributes::DEFAULT;
+            attributes.apply(params);
+            attributes
+        };
+        assert_eq!(apply(&[]), Attributes::DEFAULT);
+        assert_eq!(apply(&[31]).color, 0x01);
+        assert_eq!(apply(&[31, 44]).color, 0x41);
+        assert_eq!(apply(&[91, 104]).color, 0xC9);
+        assert_eq!(
+            apply(&[1, 31]),
+            Attributes {
+                color: 0x09,
+                bold: true
+            }
+        );
+        assert_eq!(apply(&[1, 31, 22]).color, 0x01);
+        assert_eq!(apply(&[31, 44, 0]), Attributes::DEFAULT);
+        assert_eq!(apply(&[31, 44, 39, 49]), Attributes::DEFAULT);
+        // unknown ones are skipped
+        assert_eq!(apply(&[4, 32]).color, 0x02);
+    }
+}
@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate cpu_lib;
+
+use core::ptr::addr_of_mut;
+
+use cpu_lib::framebuffer::Framebuffer;
+use cpu_lib::monitor::{install_shadow, remove_shadow};
+use cpu_lib::prelude::*;
+use cpu_lib::scrollback;
+
+const FRAMES: usize = 32;
+
+static mut FRAMEBUFFER: Framebuffer = Framebuffer::new();
+
+// like a game: a border that stays, and a pattern that moves a little
+fn draw_frame(display: &mut impl TextDisplay, frame: usize) {
+    let (width, height) = display.size();
+    for y in 0..height {
+        for x in 0..width {
+            let ch = if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+                b'#'
+            } else if (x + y + frame).is_multiple_of(16) {
+                b'@'
+            } else {
+                b' '
+            };
+            display.set_cell(x, y, ch, 0x07);
+        }
+    }
+    display.flush();
+}
+
+fn scroll_frame(display: &mut impl TextDisplay, frame: usize) {
+    let (_, height) = display.size();
+    display.put_str(1, height - 1, b"scrolled", (frame % 7 + 1) as u8);
+    monitor::scroll_down();
+    display.flush();
+}
+
+fn cycles_per_frame(mut run: impl FnMut(usize)) -> usize {
+    let start = read_cycles();
+    (0..FRAMES).for_each(&mut run);
+    read_cycles().wrapping_sub(start) / FRAMES
+}
+
+// (direct, with the shadow)
+fn measure(mut run: impl FnMut(&mut Board, usize)) -> (usize, usize) {
+    let direct = cycles_per_frame(|frame| run(&mut Board, frame));
+
+    install_shadow(unsafe { &mut *addr_of_mut!(FRAMEBUFFER) });
+    let shadow = cycles_per_frame(|frame| run(&mut Board, frame));
+    remove_shadow();
+
+    (direct, shadow)
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    // `scroll_down` would also copy each line into a scrollback, both with
+    // and without the shadow, so none is installed while measuring
+    scrollback::uninstall();
+    let redraw = measure(draw_frame);
+    let scroll = measure(scroll_frame);
+
+    monitor::clear_screen();
+    println!("[fbbench] cycles per frame, {} frames each", FRAMES);
+    println!(
+        "redraw: {:>8} direct, {:>8} with the shadow",
+        redraw.0, redraw.1
+    );
+    println!(
+        "scroll: {:>8} direct, {:>8} with the shadow",
+        scroll.0, scroll.1
+    );
+
+    0
+}
@@ -7,6 +7,8 @@ extern crate cpu_lib;
 use cpu_lib::prelude::*;
 
 fn read_number() -> u32 {
+    let mut buttons = ButtonEvents::new();
+    let mut keypad = Keypad::new();
     let mut v = 0;
     let mut en = 0;
 
@@ -15,25 +17,29 @@ fn read_number() -> u32 {
     }
 
     loop {
-        while !keyboard_ready() {
-            if read_button(Button::Left) {
-                en >>= 1;
-                v /= 10;
-                set_tube_value_option(Some(v));
-                set_tube_enable(en);
-                while read_button(Button::Left) {}
-            }
-            if read_button(Button::Center) {
-                while read_button(Button::Center) {}
-                return v;
+        if let Some(event) = buttons.poll() {
+            match (event.button, event.kind) {
+                (Button::Left, ButtonEventKind::Pressed) => {
+                    en >>= 1;
+                    v /= 10;
+                    set_tube_value_option(Some(v));
+                    set_tube_enable(en);
+                }
+                (Button::Center, ButtonEventKind::Pressed) => return v,
+                _ => {}
             }
         }
 
-        en = en << 1 | 1;
-        let key = read_keyboard().as_number().unwrap();
-        v = v * 10 + key;
-        set_tube_value_option(Some(v));
-        set_tube_enable(en);
+        let key = keypad
+            .poll()
+            .filter(|event| event.kind == KeyEventKind::Down)
+            .and_then(|event| event.key.as_number());
+        if let Some(key) = key {
+            en = en << 1 | 1;
+            v = v * 10 + key;
+            set_tube_value_option(Some(v));
+            set_tube_enable(en);
+        }
     }
 }
 
@@ -1,42 +1,238 @@
 #![no_std]
 #![no_main]
 
-use core::ptr::write_volatile;
+use core::ptr::{read_volatile, write_volatile};
+use core::str::SplitWhitespace;
+use core::time::Duration;
 
+#[macro_use]
 extern crate cpu_lib;
 
 use cpu_lib::prelude::*;
+use cpu_lib::reset;
 
 const APP_BASE_ADDRESS: usize = 0x00008000;
 
+// give up on a transfer if the host stalls this long between two bytes
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+const HELP: &str = "\
+[loader] Commands (numbers are decimal, or hex with 0x):\r
+  <size>                    load a program to 0x8000 and call it\r
+  r <addr>                  read a word\r
+  w <addr> <value>          write a word\r
+  d <addr> [words]          dump words\r
+  f <addr> <words> <value>  fill words\r
+  c <addr> <addr> <words>   compare words\r
+  j [addr]                  call an address (default 0x8000)\r
+  u <addr> <bytes>          receive raw bytes to memory\r
+  s <addr> <bytes>          send raw bytes from memory\r
+  h                         this help\r
+";
+
+fn parse_number(s: &str) -> Option<usize> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn next_number(args: &mut SplitWhitespace) -> Option<usize> {
+    args.next().and_then(parse_number)
+}
+
+// word accesses must be aligned, this also keeps MMIO happy
+fn next_word_address(args: &mut SplitWhitespace) -> Option<usize> {
+    next_number(args).filter(|addr| addr % 4 == 0)
+}
+
+// whether `count` items of `size` bytes from `addr` end before the address wraps
+fn fits(addr: usize, count: usize, size: usize) -> bool {
+    count
+        .checked_mul(size)
+        .is_some_and(|bytes| bytes == 0 || addr.checked_add(bytes - 1).is_some())
+}
+
+// the reply for a range that does not fit, the command is not run
+fn wraps() -> Option<()> {
+    print_str("[loader] The range wraps past the end of memory\r\n");
+    Some(())
+}
+
+fn read_word(addr: usize) -> u32 {
+    unsafe { read_u32(addr) }
+}
+
+fn write_word(addr: usize, data: u32) {
+    unsafe { write_u32(addr, data) }
+}
+
+fn call(addr: usize) {
+    println!("[loader] Calling {:#010x}...", addr);
+    let code: extern "C" fn() = unsafe { core::mem::transmute(addr as *const ()) };
+    (code)();
+
+    monitor::clear_screen();
+    print_str("[loader] Program exited\r\n\r\n");
+}
+
+// returns the number of bytes received before a timeout
+fn receive(addr: usize, size: usize) -> Result<(), usize> {
+    for i in 0..size {
+        let data = get_char_timeout(false, RECEIVE_TIMEOUT).map_err(|_| i)?;
+        unsafe { write_volatile((addr + i) as *mut u8, data) }
+    }
+    Ok(())
+}
+
+fn receive_reporting(addr: usize, size: usize) -> bool {
+    match receive(addr, size) {
+        Ok(()) => true,
+        Err(received) => {
+            println!(
+                "[loader] Transfer timed out after {} of {} bytes",
+                received, size
+            );
+            false
+        }
+    }
+}
+
+fn send(addr: usize, size: usize) {
+    // raw, so only to the UART
+    (0..size).for_each(|i| uart::write(unsafe { read_volatile((addr + i) as *const u8) }));
+    uart::flush();
+}
+
+fn dump(addr: usize, words: usize) {
+    for line in (0..words).step_by(4) {
+        print!("{:08x}:", addr + line * 4);
+        for i in line..words.min(line + 4) {
+            print!(" {:08x}", read_word(addr + i * 4));
+        }
+        print_str("\r\n");
+    }
+}
+
+fn compare(a: usize, b: usize, words: usize) {
+    let mut differences = 0;
+    for i in 0..words {
+        let (x, y) = (read_word(a + i * 4), read_word(b + i * 4));
+        if x != y {
+            if differences < 16 {
+                println!("{:08x}: {:08x}  {:08x}: {:08x}", a + i * 4, x, b + i * 4, y);
+            }
+            differences += 1;
+        }
+    }
+    println!("[loader] {} of {} words differ", differences, words);
+}
+
+// returns `None` if the command or its arguments are wrong
+fn run_command(line: &str) -> Option<()> {
+    let mut args = line.split_whitespace();
+    let command = args.next()?;
+
+    // a bare size is the original protocol, used by `make send`
+    if let Some(size) = parse_number(command) {
+        if !fits(APP_BASE_ADDRESS, size, 1) {
+            return wraps();
+        }
+        print_str("[loader] Transfer the data below\r\n");
+        if receive_reporting(APP_BASE_ADDRESS, size) {
+            print_str("[loader] Program loaded!\r\n");
+            call(APP_BASE_ADDRESS);
+        }
+        return Some(());
+    }
+
+    let args = &mut args;
+    match command {
+        "r" => {
+            let addr = next_word_address(args)?;
+            println!("{:08x}: {:08x}", addr, read_word(addr));
+        }
+        "w" => {
+            let addr = next_word_address(args)?;
+            let value = next_number(args)?;
+            write_word(addr, value as u32);
+        }
+        "d" => {
+            let addr = next_word_address(args)?;
+            let words = args.next().map_or(Some(16), parse_number)?;
+            if !fits(addr, words, 4) {
+                return wraps();
+            }
+            dump(addr, words);
+        }
+        "f" => {
+            let addr = next_word_address(args)?;
+            let words = next_number(args)?;
+            let value = next_number(args)?;
+            if !fits(addr, words, 4) {
+                return wraps();
+            }
+            (0..words).for_each(|i| write_word(addr + i * 4, value as u32));
+        }
+        "c" => {
+            let a = next_word_address(args)?;
+            let b = next_word_address(args)?;
+            let words = next_number(args)?;
+            if !fits(a, words, 4) || !fits(b, words, 4) {
+                return wraps();
+            }
+            compare(a, b, words);
+        }
+        "j" => {
+            let addr = match args.next() {
+                Some(addr) => parse_number(addr).filter(|addr| addr % 4 == 0)?,
+                None => APP_BASE_ADDRESS,
+            };
+            call(addr);
+        }
+        "u" => {
+            let addr = next_number(args)?;
+            let size = next_number(args)?;
+            if !fits(addr, size, 1) {
+                return wraps();
+            }
+            println!("[loader] Transfer {} bytes below", size);
+            if receive_reporting(addr, size) {
+                print_str("[loader] Done\r\n");
+            }
+        }
+        "s" => {
+            let addr = next_number(args)?;
+            let size = next_number(args)?;
+            if !fits(addr, size, 1) {
+                return wraps();
+            }
+            send(addr, size);
+        }
+        "h" | "?" => print_str(HELP),
+        _ => return None,
+    }
+    Some(())
+}
+
 #[no_mangle]
 fn main() -> i32 {
     print_str("[loader] Welcome to the program loader!\r\n");
-    let mut buf = [0; 16];
+    if let reset::ResetCause::Reset { pc } = reset::cause() {
+        println!("[loader] Reset at pc {:#010x}", pc);
+    }
+    print_str("[loader] Type a program size to load it, or h for the monitor commands\r\n");
+    let mut buf = [0; 64];
 
     loop {
-        print_str("[loader] Program size (bytes): ");
+        print_str("[loader] > ");
         let line = read_line(&mut buf);
 
-        let size: usize = if let Ok(size) = line.parse() {
-            size
-        } else {
-            print_str("[loader] Invalid number!");
-            continue
-        };
-
-        print_str("[loader] Transfer the data below\r\n");
-        unsafe {
-            core::slice::from_raw_parts_mut(APP_BASE_ADDRESS as *mut u8, size)
-                .iter_mut()
-                .for_each(|addr| write_volatile(addr, get_char(false)));
+        if line.trim().is_empty() {
+            continue;
+        }
+        if run_command(line).is_none() {
+            print_str("[loader] Invalid command! Type h for help\r\n");
         }
-
-        print_str("[loader] Program loaded! Calling...\r\n");
-        let code: extern "C" fn() = unsafe { core::mem::transmute(APP_BASE_ADDRESS as *const ()) };
-        (code)();
-
-        monitor::clear_screen();
-        print_str("[loader] Program exited\r\n\r\n");
     }
 }
@@ -9,17 +9,12 @@ use cpu_lib::prelude::*;
 #[no_mangle]
 fn main() -> i32 {
     println!("Hello, world!");
-    let mut buf = [0; 16];
 
     monitor::set_color(1);
-    print!("n1: ");
-    let line = read_line(&mut buf);
-    let n1: i32 = line.parse().unwrap();
+    let n1 = scan!("n1: ", i32);
 
     monitor::set_color(2);
-    print!("n2: ");
-    let line = read_line(&mut buf);
-    let n2: i32 = line.parse().unwrap();
+    let n2 = scan!("n2: ", i32);
 
     monitor::set_color(3);
     println!("n1 = {}", n1);
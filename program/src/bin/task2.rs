@@ -34,24 +34,17 @@ fn read_number() -> u32 {
 }
 */
 
-fn read_number() -> u8 {
-    while !read_button(Button::Center) {}
+fn read_number(buttons: &mut ButtonEvents) -> u8 {
+    buttons.wait_press(Button::Center);
 
-    let mut v = 0;
-    for i in 0..8 {
-        v = v << 1 | (read_switch(7 - i) as u8);
-    }
+    let v = switches(0..8);
     set_led_number(v);
 
-    while read_button(Button::Center) {}
-
     v
 }
 
 fn set_led_number(v: u8) {
-    for i in 0..8 {
-        set_led(i, (v & (1 << i)) > 0);
-    }
+    leds(0..8).set(v as u32);
 }
 
 fn accumulate(v: u8, t: u8) -> u8 {
@@ -73,14 +66,17 @@ fn accumulate(v: u8, t: u8) -> u8 {
     }
 }
 
-fn task_accumulative(b: u8) {
-    let v1 = read_number();
+fn task_accumulative(buttons: &mut ButtonEvents, b: u8) {
+    let v1 = read_number(buttons);
     if (v1 as i8) < 0 {
+        let mut effects = LedEffects::new();
+        let blink = Effect::Blink {
+            on: Duration::from_millis(500),
+            off: Duration::from_millis(500),
+        };
+        effects.set_mask(0..8, v1 as u32, blink, Effect::Off);
         loop {
-            set_led_number(v1);
-            sleep(Duration::from_millis(500));
-            set_led_number(0);
-            sleep(Duration::from_millis(500));
+            effects.poll();
         }
     }
     let w = accumulate(v1, b - 1);
@@ -92,9 +88,9 @@ fn task_accumulative(b: u8) {
     }
 }
 
-fn task_arithmetic(b: u8) {
-    let v1 = read_number() as i8;
-    let v2 = read_number() as i8;
+fn task_arithmetic(buttons: &mut ButtonEvents, b: u8) {
+    let v1 = read_number(buttons) as i8;
+    let v2 = read_number(buttons) as i8;
 
     match b {
         0b100 => {
@@ -109,18 +105,22 @@ fn task_arithmetic(b: u8) {
         }
         0b110 => {
             let r = ((v1 as i16) * (v2 as i16)) as u16;
-            for i in 0..16 {
-                set_led(i, (r & (1 << i)) > 0);
-            }
+            leds(0..16).set(r as u32);
         }
         0b111 => {
             let q = v1 / v2;
             let r = v1 % v2;
+            let patterns = [q as u8 as u32, r as u8 as u32];
+            let mut effects = LedEffects::new();
+            effects.start(Animation::Sequence {
+                start: 0,
+                len: 8,
+                patterns: &patterns,
+                step: Duration::from_secs(5),
+                repeat: true,
+            });
             loop {
-                set_led_number(q as u8);
-                sleep(Duration::from_secs(5));
-                set_led_number(r as u8);
-                sleep(Duration::from_secs(5));
+                effects.poll();
             }
         }
         _ => {}
@@ -129,23 +129,21 @@ fn task_arithmetic(b: u8) {
 
 #[no_mangle]
 fn main() -> i32 {
+    let mut buttons = ButtonEvents::new();
+
     loop {
         println!("new task!");
 
-        while !read_button(Button::Center) {}
+        buttons.wait_press(Button::Center);
 
-        let b: u8 =
-            read_switch(16) as u8 | ((read_switch(17) as u8) << 1) | ((read_switch(18) as u8) << 2);
-        (0..8).for_each(|x| set_led(x + 16, false));
-        set_led(16 + b as usize, true);
+        let b = switches(16..19);
+        leds(16..24).set(1 << b);
         println!("b = {}", b);
 
-        while read_button(Button::Center) {}
-
         if b < 4 {
-            task_accumulative(b);
+            task_accumulative(&mut buttons, b);
         } else {
-            task_arithmetic(b);
+            task_arithmetic(&mut buttons, b);
         }
     }
 }
@@ -1,6 +1,7 @@
 use core::time::Duration;
 
-use cpu_lib::time::{sleep, Instant};
+use cpu_lib::platform::Platform;
+use cpu_lib::time::Instant;
 
 use crate::ui::Ui;
 
@@ -8,7 +9,8 @@ use super::logic::{End, Logic, TickResult};
 
 const TICKS_PER_SECOND: f64 = 60f64;
 
-pub struct GameLoop {
+pub struct GameLoop<'a, P> {
+    platform: &'a mut P,
     tick_duration: Duration,
     previous: Instant,
     current: Instant,
@@ -17,11 +19,12 @@ pub struct GameLoop {
     ui: Ui,
 }
 
-impl GameLoop {
-    pub fn new(logic: Logic, ui: Ui) -> Self {
-        let instant = Instant::now();
+impl<'a, P: Platform> GameLoop<'a, P> {
+    pub fn new(platform: &'a mut P, logic: Logic, ui: Ui) -> Self {
+        let instant = platform.now();
 
         GameLoop {
+            platform,
             tick_duration: Duration::from_secs_f64(1.0 / TICKS_PER_SECOND),
             previous: instant,
             current: instant,
@@ -32,7 +35,8 @@ impl GameLoop {
     }
 
     pub fn run(&mut self) -> End {
-        self.ui.draw_border();
+        self.ui.draw_border(self.platform);
+        self.platform.flush();
         loop {
             if let Some(end) = self.iterate() {
                 return end;
@@ -41,7 +45,7 @@ impl GameLoop {
     }
 
     fn iterate(&mut self) -> Option<End> {
-        self.current = Instant::now();
+        self.current = self.platform.now();
 
         let mut elapsed = self.current - self.previous;
         self.previous = self.current;
@@ -55,12 +59,17 @@ impl GameLoop {
         if self.accumulated >= self.tick_duration {
             self.accumulated -= self.tick_duration;
 
-            match self.logic.update() {
+            match self.logic.update(self.platform) {
                 TickResult::End(end) => {
                     return Some(end);
                 }
-                TickResult::Phase(phase) => self.ui.draw(&phase),
+                TickResult::Phase(phase) => {
+                    self.ui.draw(self.platform, &phase);
+                    self.platform.flush();
+                }
             };
+            // drawing takes a while, keep the UART from overflowing
+            self.platform.poll();
         } else {
             self.idle(self.tick_duration - self.accumulated);
         }
@@ -68,7 +77,7 @@ impl GameLoop {
         None
     }
 
-    fn idle(&self, difference: Duration) {
-        sleep(difference);
+    fn idle(&mut self, difference: Duration) {
+        self.platform.delay(difference);
     }
 }
@@ -95,3 +95,25 @@ pub enum ClearedLines {
     Triple,
     Tetrs,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_and_levels_up() {
+        let mut level = Level::new(0);
+        level.up(&ClearedLines::Single);
+        assert_eq!((level.score, level.cleared_lines), (40, 1));
+        level.up(&ClearedLines::Tetrs);
+        level.up(&ClearedLines::Tetrs);
+        assert_eq!(level.current, 0);
+        // the 10th line
+        level.up(&ClearedLines::Double);
+        assert_eq!(level.current, 1);
+        assert_eq!(level.score, 40 + 2 * 1200 + 100);
+        level.up(&ClearedLines::Triple);
+        assert_eq!(level.score, 40 + 2 * 1200 + 100 + 2 * 300);
+        assert_eq!(level.required_ticks(), 43);
+    }
+}
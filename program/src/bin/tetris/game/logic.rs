@@ -1,6 +1,9 @@
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 
+use cpu_lib::platform::Platform;
+use cpu_lib::rng::PlatformRng;
+
 use crate::input::{collect_inputs, Input};
 
 use super::{phase::running::Running, phase::Phase};
@@ -32,16 +35,16 @@ pub struct Logic {
 }
 
 impl Logic {
-    pub fn new() -> Self {
+    pub fn new(platform: &mut impl Platform) -> Self {
         Self {
             phase: Phase::Running(Box::new(Running {
-                state: GameState::new(1),
+                state: GameState::new(1, &mut PlatformRng(platform)),
             })),
         }
     }
 
-    pub fn update(&mut self) -> TickResult {
-        let inputs: Vec<Input> = collect_inputs();
+    pub fn update(&mut self, platform: &mut impl Platform) -> TickResult {
+        let inputs: Vec<Input> = collect_inputs(platform);
 
         if let Some(end) = self.check_for_end(&inputs) {
             return TickResult::End(end);
@@ -49,7 +52,7 @@ impl Logic {
 
         match &mut self.phase {
             Phase::Running(running) => {
-                if let Some(finished) = running.handle(&inputs) {
+                if let Some(finished) = running.handle(&inputs, &mut PlatformRng(platform)) {
                     self.phase = Phase::Finished(finished);
                 }
             }
@@ -1,3 +1,5 @@
+use rand::RngCore;
+
 use crate::{game::state::GameState, input::Input};
 
 use super::running::Running;
@@ -6,7 +8,7 @@ use super::running::Running;
 pub struct Menu {}
 
 impl Menu {
-    pub fn handle(&self, inputs: &[Input]) -> Option<Box<Running>> {
+    pub fn handle(&self, inputs: &[Input], rng: &mut impl RngCore) -> Option<Box<Running>> {
         let input = inputs.iter().find_map(|&x| match x {
             Input::Number(number) => Some(number),
             _ => None,
@@ -14,7 +16,7 @@ impl Menu {
 
         input.map(|level| {
             Box::new(Running {
-                state: GameState::new(level),
+                state: GameState::new(level, rng),
             })
         })
     }
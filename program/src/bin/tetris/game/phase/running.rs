@@ -1,6 +1,8 @@
 use alloc::boxed::Box;
 use core::mem::swap;
 
+use rand::RngCore;
+
 use crate::{
     game::{state::GameState, tetromino::Tetromino},
     input::Input,
@@ -14,7 +16,7 @@ pub struct Running {
 }
 
 impl Running {
-    pub fn handle(&mut self, inputs: &[Input]) -> Option<Box<Finished>> {
+    pub fn handle(&mut self, inputs: &[Input], rng: &mut impl RngCore) -> Option<Box<Finished>> {
         if self.state.is_finished() {
             return Some(Box::new(Finished {
                 state: self.state.clone(),
@@ -29,7 +31,7 @@ impl Running {
             self.state.level.up(&cleared_lines);
 
             swap(&mut self.state.current, &mut self.state.next);
-            self.state.next = Tetromino::next();
+            self.state.next = Tetromino::next(rng);
         }
 
         self.state.preview = self.state.determine_preview();
@@ -2,6 +2,7 @@ use alloc::collections::VecDeque;
 use alloc::vec;
 
 use cpu_lib::monitor::Color;
+use rand::RngCore;
 
 use super::{
     level::{ClearedLines, Level},
@@ -30,11 +31,11 @@ pub struct GameState {
 }
 
 impl GameState {
-    pub fn new(level: u32) -> Self {
+    pub fn new(level: u32, rng: &mut impl RngCore) -> Self {
         let mut state = Self {
             level: Level::new(level),
-            current: Tetromino::next(),
-            next: Tetromino::next(),
+            current: Tetromino::next(rng),
+            next: Tetromino::next(rng),
             preview: None,
             ticks: 0,
             field: VecDeque::from(vec![[Square::Empty; 10]; 20]),
@@ -192,3 +193,60 @@ pub enum Collision {
     OutOfBounds,
     WithBlock,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(current: Tetromino) -> GameState {
+        GameState {
+            level: Level::new(0),
+            current,
+            next: Tetromino::o(),
+            preview: None,
+            ticks: 0,
+            field: VecDeque::from(vec![[Square::Empty; FIELD_WIDTH]; FIELD_HEIGHT]),
+        }
+    }
+
+    #[test]
+    fn drops_and_solidifies() {
+        let mut state = state(Tetromino::o());
+        assert_eq!(state.determine_preview().unwrap().coords.y, 17);
+        state.drop();
+        for (x, y) in [(4, 18), (5, 18), (4, 19), (5, 19)] {
+            assert_eq!(state.field[y][x], Square::Occupied(Color::Yellow));
+        }
+        assert_eq!(state.field[17][4], Square::Empty);
+    }
+
+    #[test]
+    fn kicks_back_at_the_wall() {
+        let mut state = state(Tetromino::i());
+        (0..5).for_each(|_| state.move_left());
+        assert_eq!(state.current.coords.x, 0);
+        (0..10).for_each(|_| state.move_right());
+        assert_eq!(state.current.coords.x, 6);
+
+        // stands up once there is room below the top
+        (0..2).for_each(|_| state.current.move_down());
+        state.rotate();
+        let blocks = state.current.offset_blocks();
+        assert!(blocks.iter().all(|block| block.vec.x == blocks[0].vec.x));
+    }
+
+    #[test]
+    fn clears_full_lines() {
+        let mut state = state(Tetromino::o());
+        let full = [Square::Occupied(Color::Red); FIELD_WIDTH];
+        state.field[19] = full;
+        state.field[18] = full;
+        state.field[17][0] = Square::Occupied(Color::Blue);
+        assert!(matches!(state.clear_lines(), ClearedLines::Double));
+        assert_eq!(state.field.len(), FIELD_HEIGHT);
+        assert_eq!(state.field[19][0], Square::Occupied(Color::Blue));
+        assert!(state.field[18]
+            .iter()
+            .all(|square| *square == Square::Empty));
+    }
+}
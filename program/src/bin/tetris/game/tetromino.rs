@@ -1,8 +1,8 @@
 use core::hash::{Hash, Hasher};
-use rand::Rng;
+use rand::{Rng, RngCore};
 // use tui::style::Color;
 
-use cpu_lib::{monitor::Color, rng};
+use cpu_lib::monitor::Color;
 
 use super::math::{Matrix2D, Vector2D, ROTATE_CCW, ROTATE_CW};
 
@@ -163,8 +163,8 @@ impl Tetromino {
         }
     }
 
-    pub fn next() -> Tetromino {
-        match rng::rng().gen_range(0..7) {
+    pub fn next(rng: &mut impl RngCore) -> Tetromino {
+        match rng.gen_range(0..7) {
             0 => Tetromino::i(),
             1 => Tetromino::o(),
             2 => Tetromino::t(),
@@ -1,6 +1,6 @@
 use alloc::vec::Vec;
 
-use cpu_lib::uart;
+use cpu_lib::platform::Platform;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Input {
@@ -38,11 +38,11 @@ impl TryFrom<char> for Input {
     }
 }
 
-pub fn collect_inputs() -> Vec<Input> {
+pub fn collect_inputs(platform: &mut impl Platform) -> Vec<Input> {
     let mut v = Vec::new();
-    while uart::input_ready() {
-        if let Ok(input) = Input::try_from(uart::read() as char) {
-            println!("Input: {:?}", input);
+    while let Some(byte) = platform.uart_read() {
+        if let Ok(input) = Input::try_from(byte as char) {
+            log::debug!("Input: {:?}", input);
             v.push(input);
         }
     }
@@ -1,11 +1,16 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), no_main)]
 
 extern crate alloc;
 
-#[macro_use]
 extern crate cpu_lib;
 
+use core::ptr::addr_of_mut;
+
+use cpu_lib::framebuffer::Framebuffer;
+use cpu_lib::logger::{self, Config};
+use cpu_lib::monitor;
+use cpu_lib::platform::Board;
 use game::logic::End;
 use tetrs::Tetrs;
 
@@ -14,10 +19,23 @@ mod input;
 mod tetrs;
 mod ui;
 
-#[no_mangle]
+static mut FRAMEBUFFER: Framebuffer = Framebuffer::new();
+
+#[cfg_attr(not(test), no_mangle)]
 fn main() -> i32 {
-    let game = Tetrs::new();
+    // switches 21 to 23 set the log level, 4 shows the input
+    logger::init(Config {
+        switches: Some(21..24),
+        ..Config::DEFAULT
+    });
+
+    // draw in RAM, only the changes go to the screen
+    monitor::install_shadow(unsafe { &mut *addr_of_mut!(FRAMEBUFFER) });
+
+    let mut game = Tetrs::new(Board);
     while game.run() != End::Quit {}
 
+    monitor::remove_shadow();
+
     0
 }
@@ -1,5 +1,6 @@
-use cpu_lib::console::set_screen_print;
-use cpu_lib::monitor::monitor::clear_screen;
+use cpu_lib::console::{set_sinks, sinks, Sinks, Stream};
+use cpu_lib::platform::Platform;
+use cpu_lib::tui::Style;
 
 use crate::{
     game::{
@@ -9,15 +10,17 @@ use crate::{
     ui::Ui,
 };
 
-pub struct Tetrs;
+pub struct Tetrs<P> {
+    platform: P,
+}
 
-impl Tetrs {
-    pub fn new() -> Tetrs {
-        Tetrs
+impl<P: Platform> Tetrs<P> {
+    pub fn new(platform: P) -> Tetrs<P> {
+        Tetrs { platform }
     }
 
-    pub fn run(&self) -> End {
-        clear_screen();
+    pub fn run(&mut self) -> End {
+        self.platform.clear(Style::DEFAULT.as_u8());
 
         // println!("Note that the behavior of \\xXX on byte-string literals matches the expectations established by the C program in Behavior of xXX in C; that is good. The problem is the behavior of \\xXX for XX > 0x7F in string-literal contexts, namely in the fourth and fifth examples where the .as_bytes() invocations are showing that the underlying byte array has two elements instead of one.");
         // println!("Currently, we allow any traits to be used for trait objects, but restrict the methods which can be called on such objects. Here, we propose instead restricting which traits can be used to make objects. Despite being less flexible, this will make for better error messages, less surprising software evolution, and (hopefully) better design. The motivation for the proposed change is stronger due to part of the DST changes.");
@@ -29,11 +32,16 @@ impl Tetrs {
 
         // loop {}
 
-        set_screen_print(false);
+        // text output goes to the UART only, not over the game
+        let (stdout, stderr) = (sinks(Stream::Stdout), sinks(Stream::Stderr));
+        set_sinks(Stream::Stdout, stdout.difference(Sinks::SCREEN));
+        set_sinks(Stream::Stderr, stderr.difference(Sinks::SCREEN));
 
-        let result = GameLoop::new(Logic::new(), Ui::default()).run();
+        let logic = Logic::new(&mut self.platform);
+        let result = GameLoop::new(&mut self.platform, logic, Ui::default()).run();
 
-        set_screen_print(true);
+        set_sinks(Stream::Stdout, stdout);
+        set_sinks(Stream::Stderr, stderr);
         result
     }
 }
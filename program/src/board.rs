@@ -1,9 +1,17 @@
-use core::ptr::{read_volatile, write_volatile};
+use core::ops::Range;
 
-const SWITCH_BASEADDR: *mut u32 = 0xFFFFF100 as *mut u32;
-const LED_BASEADDR: *mut u32 = 0xFFFFF200 as *mut u32;
+use crate::mmio;
+use crate::time::{Deadline, TimedOut};
 
-#[derive(Debug)]
+pub const SWITCH_COUNT: usize = 24;
+pub const LED_COUNT: usize = 24;
+
+// LEDs are write-only, so remember what was written.
+// Only the bits in `LED_KNOWN` are valid, since a previous program may have left LEDs on.
+static mut LED_STATE: u32 = 0;
+static mut LED_KNOWN: u32 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Keyboard {
     _0 = 2,
@@ -24,16 +32,72 @@ pub enum Keyboard {
     Number = 1,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(usize)]
 pub enum Button {
-    Center = 0xFFFFF01C,
-    Up = 0xFFFFF020,
-    Down = 0xFFFFF024,
-    Left = 0xFFFFF028,
-    Right = 0xFFFFF02C,
+    Center = 0,
+    Up = 1,
+    Down = 2,
+    Left = 3,
+    Right = 4,
+}
+
+impl Button {
+    pub const ALL: [Button; 5] = [
+        Button::Center,
+        Button::Up,
+        Button::Down,
+        Button::Left,
+        Button::Right,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidKeyCode(pub u8);
+
+impl TryFrom<u8> for Keyboard {
+    type Error = InvalidKeyCode;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        match code {
+            0 => Ok(Keyboard::D),
+            1 => Ok(Keyboard::Number),
+            2 => Ok(Keyboard::_0),
+            3 => Ok(Keyboard::Star),
+            4 => Ok(Keyboard::C),
+            5 => Ok(Keyboard::_9),
+            6 => Ok(Keyboard::_8),
+            7 => Ok(Keyboard::_7),
+            8 => Ok(Keyboard::B),
+            9 => Ok(Keyboard::_6),
+            10 => Ok(Keyboard::_5),
+            11 => Ok(Keyboard::_4),
+            12 => Ok(Keyboard::A),
+            13 => Ok(Keyboard::_3),
+            14 => Ok(Keyboard::_2),
+            15 => Ok(Keyboard::_1),
+            code => Err(InvalidKeyCode(code)),
+        }
+    }
 }
 
 impl Keyboard {
+    // the physical layout:
+    //   1 2 3 A
+    //   4 5 6 B
+    //   7 8 9 C
+    //   * 0 # D
+    pub const LAYOUT: [[Keyboard; 4]; 4] = [
+        [Keyboard::_1, Keyboard::_2, Keyboard::_3, Keyboard::A],
+        [Keyboard::_4, Keyboard::_5, Keyboard::_6, Keyboard::B],
+        [Keyboard::_7, Keyboard::_8, Keyboard::_9, Keyboard::C],
+        [Keyboard::Star, Keyboard::_0, Keyboard::Number, Keyboard::D],
+    ];
+
+    pub fn code(&self) -> u8 {
+        *self as u8
+    }
+
     pub fn as_number(&self) -> Option<u32> {
         match self {
             Keyboard::_0 => Some(0),
@@ -49,41 +113,252 @@ impl Keyboard {
             _ => None,
         }
     }
+
+    pub fn as_hex_digit(&self) -> Option<u32> {
+        match self {
+            Keyboard::A => Some(0xA),
+            Keyboard::B => Some(0xB),
+            Keyboard::C => Some(0xC),
+            Keyboard::D => Some(0xD),
+            key => key.as_number(),
+        }
+    }
+
+    pub fn as_char(&self) -> char {
+        match self {
+            Keyboard::Star => '*',
+            Keyboard::Number => '#',
+            key => char::from_digit(key.as_hex_digit().unwrap(), 16)
+                .unwrap()
+                .to_ascii_uppercase(),
+        }
+    }
+
+    // (row, column) on the physical keypad, counting from the top-left
+    pub fn position(&self) -> (usize, usize) {
+        // the hardware encodes a key as `15 - (row * 4 + column)`
+        let index = 15 - self.code() as usize;
+        (index / 4, index % 4)
+    }
+
+    pub fn row(&self) -> usize {
+        self.position().0
+    }
+
+    pub fn column(&self) -> usize {
+        self.position().1
+    }
 }
 
+/// # Safety
+///
+/// `addr` has to be a word-aligned address that is valid to read, in RAM
+/// or MMIO. Reading some registers, e.g. the UART, consumes data.
 #[inline]
 pub unsafe fn read_u32(addr: usize) -> u32 {
-    read_volatile(addr as *mut u32)
+    mmio::read_raw(addr)
 }
 
+/// # Safety
+///
+/// `addr` has to be a word-aligned address that is valid to write, and
+/// nothing else may rely on what is stored there.
 #[inline]
 pub unsafe fn write_u32(addr: usize, data: u32) {
-    write_volatile(addr as *mut u32, data)
+    mmio::write_raw(addr, data)
+}
+
+#[inline]
+fn bit_mask(range: &Range<usize>) -> u32 {
+    assert!(range.start <= range.end && range.end <= 32);
+    let len = range.end - range.start;
+    let mask = if len == 32 { u32::MAX } else { (1 << len) - 1 };
+    mask << range.start
 }
 
 pub fn read_switch(index: usize) -> bool {
-    assert!(index < 24);
-    unsafe { SWITCH_BASEADDR.add(index).read_volatile() > 0 }
+    mmio::SWITCHES.read(index) > 0
+}
+
+fn read_range(range: Range<usize>) -> u32 {
+    assert!(range.end <= SWITCH_COUNT);
+    range.rev().fold(0, |v, i| v << 1 | read_switch(i) as u32)
+}
+
+// bit `i` is switch `i`
+pub fn read_switches() -> u32 {
+    read_range(0..SWITCH_COUNT)
+}
+
+// up to 8 switches as a number, bit 0 is the first switch
+pub fn switches(range: Range<usize>) -> u8 {
+    assert!(range.len() <= 8);
+    read_range(range) as u8
 }
 
 pub fn set_led(index: usize, enable: bool) {
-    assert!(index < 24);
-    unsafe { LED_BASEADDR.add(index).write_volatile(enable as u32) }
+    mmio::LEDS.write(index, enable as u32);
+    unsafe {
+        LED_STATE = LED_STATE & !(1 << index) | (enable as u32) << index;
+        LED_KNOWN |= 1 << index;
+    }
+}
+
+// bit `i` is LED `i`, LEDs already in the requested state are not written
+pub fn write_leds(mask: u32) {
+    write_leds_masked(mask, bit_mask(&(0..LED_COUNT)));
+}
+
+// only updates LEDs selected by `select`
+pub fn write_leds_masked(value: u32, select: u32) {
+    let stale = unsafe { (LED_STATE ^ value) | !LED_KNOWN } & select & bit_mask(&(0..LED_COUNT));
+    (0..LED_COUNT)
+        .filter(|i| stale & (1 << i) != 0)
+        .for_each(|i| set_led(i, value & (1 << i) != 0));
+}
+
+// as last written by this program
+pub fn read_leds() -> u32 {
+    unsafe { LED_STATE }
+}
+
+pub fn leds(range: Range<usize>) -> LedRange {
+    assert!(range.end <= LED_COUNT);
+    LedRange { range }
+}
+
+// a group of consecutive LEDs, viewed as a number whose bit 0 is the first LED
+#[derive(Debug, Clone)]
+pub struct LedRange {
+    range: Range<usize>,
+}
+
+impl LedRange {
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    pub fn set(&self, value: u32) {
+        write_leds_masked(value << self.range.start, bit_mask(&self.range));
+    }
+
+    pub fn get(&self) -> u32 {
+        (read_leds() & bit_mask(&self.range)) >> self.range.start
+    }
+
+    pub fn clear(&self) {
+        self.set(0);
+    }
+
+    pub fn fill(&self) {
+        self.set(u32::MAX);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwitchChanges {
+    pub current: u32,
+    pub changed: u32,
+}
+
+impl SwitchChanges {
+    pub fn is_empty(&self) -> bool {
+        self.changed == 0
+    }
+
+    // switches turned on
+    pub fn rising(&self) -> u32 {
+        self.changed & self.current
+    }
+
+    // switches turned off
+    pub fn falling(&self) -> u32 {
+        self.changed & !self.current
+    }
+
+    pub fn is_changed(&self, index: usize) -> bool {
+        self.changed & (1 << index) != 0
+    }
+
+    // indices of the flipped switches
+    pub fn iter(&self) -> impl Iterator<Item = usize> {
+        let changed = self.changed;
+        (0..SWITCH_COUNT).filter(move |i| changed & (1 << i) != 0)
+    }
+}
+
+pub struct SwitchWatcher {
+    last: u32,
+}
+
+impl SwitchWatcher {
+    pub fn new() -> SwitchWatcher {
+        SwitchWatcher {
+            last: read_switches(),
+        }
+    }
+
+    pub fn last(&self) -> u32 {
+        self.last
+    }
+
+    // reports the switches flipped since the last poll
+    pub fn poll(&mut self) -> SwitchChanges {
+        let current = read_switches();
+        let changed = current ^ self.last;
+        self.last = current;
+        SwitchChanges { current, changed }
+    }
+}
+
+impl Default for SwitchWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub fn read_button(button: Button) -> bool {
-    unsafe { read_u32(button as usize) > 0 }
+    mmio::BUTTONS.read(button as usize) > 0
 }
 
 pub fn random_value() -> u32 {
-    unsafe { read_u32(0xFFFFF044) }
+    mmio::RANDOM.read()
 }
 
 pub fn keyboard_ready() -> bool {
-    unsafe { read_u32(0xFFFFF018) > 0 }
+    mmio::KEYBOARD_READY.read() > 0
+}
+
+pub fn try_read_keyboard() -> Option<Result<Keyboard, InvalidKeyCode>> {
+    if keyboard_ready() {
+        Some(Keyboard::try_from(mmio::KEYBOARD_DATA.read() as u8))
+    } else {
+        None
+    }
 }
 
+// invalid codes are dropped
 pub fn read_keyboard() -> Keyboard {
-    while !keyboard_ready() {}
-    unsafe { core::mem::transmute(read_u32(0xFFFFF014) as u8) }
+    loop {
+        if let Some(Ok(key)) = try_read_keyboard() {
+            return key;
+        }
+    }
+}
+
+// invalid codes are dropped
+pub fn read_keyboard_timeout(deadline: impl Into<Deadline>) -> Result<Keyboard, TimedOut> {
+    let deadline = deadline.into();
+    loop {
+        if let Some(Ok(key)) = try_read_keyboard() {
+            return Ok(key);
+        }
+        if deadline.is_expired() {
+            return Err(TimedOut);
+        }
+    }
 }
@@ -0,0 +1,267 @@
+use core::time::Duration;
+
+use crate::board::{read_button, Button};
+use crate::ring::RingBuffer;
+use crate::time::{Instant, Ticks};
+
+const EVENT_QUEUE_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonEventKind {
+    Pressed,
+    Released,
+    // reported once per press, with how long the button has been held
+    LongPress(Duration),
+    // reported right after the `Pressed` of the second click
+    DoubleClick,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonEvent {
+    pub button: Button,
+    pub kind: ButtonEventKind,
+    pub at: Instant,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ButtonConfig {
+    // the raw level has to be stable this long to be accepted
+    pub debounce: Duration,
+    pub long_press: Duration,
+    // maximum interval between two presses to form a double click
+    pub double_click: Duration,
+}
+
+impl Default for ButtonConfig {
+    fn default() -> Self {
+        ButtonConfig {
+            debounce: Duration::from_millis(20),
+            long_press: Duration::from_millis(800),
+            double_click: Duration::from_millis(300),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ButtonState {
+    raw: bool,
+    raw_since: Ticks,
+    pressed: bool,
+    pressed_at: Ticks,
+    long_reported: bool,
+    last_click: Option<Ticks>,
+}
+
+impl ButtonState {
+    fn new(now: Ticks) -> ButtonState {
+        ButtonState {
+            raw: false,
+            raw_since: now,
+            pressed: false,
+            pressed_at: now,
+            long_reported: false,
+            last_click: None,
+        }
+    }
+}
+
+pub struct ButtonEvents {
+    config: ButtonConfig,
+    states: [ButtonState; 5],
+    queue: RingBuffer<ButtonEvent, EVENT_QUEUE_SIZE>,
+}
+
+impl ButtonEvents {
+    pub fn new() -> ButtonEvents {
+        ButtonEvents::with_config(ButtonConfig::default())
+    }
+
+    pub fn with_config(config: ButtonConfig) -> ButtonEvents {
+        let now = Ticks::now();
+        let mut events = ButtonEvents {
+            config,
+            states: [ButtonState::new(now); 5],
+            queue: RingBuffer::new(),
+        };
+        // buttons already held at startup should not produce a press
+        for button in Button::ALL {
+            let state = &mut events.states[button as usize];
+            state.raw = read_button(button);
+            state.pressed = state.raw;
+            state.long_reported = true;
+        }
+        events
+    }
+
+    pub fn config(&self) -> &ButtonConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: ButtonConfig) {
+        self.config = config;
+    }
+
+    // samples all buttons, and queues the resulting events
+    pub fn update(&mut self) {
+        let (at, now) = (Instant::now(), Ticks::now());
+        for button in Button::ALL {
+            self.update_button(button, read_button(button), at, now);
+        }
+    }
+
+    // durations are measured with `now`, `at` is only reported
+    fn update_button(&mut self, button: Button, raw: bool, at: Instant, now: Ticks) {
+        let config = self.config;
+        let state = &mut self.states[button as usize];
+        let mut push = |kind| {
+            // the oldest events are dropped if nobody consumes them
+            self.queue.push_overwrite(ButtonEvent { button, kind, at });
+        };
+
+        if raw != state.raw {
+            state.raw = raw;
+            state.raw_since = now;
+        }
+
+        if raw != state.pressed && now.duration_since(state.raw_since) >= config.debounce {
+            state.pressed = raw;
+            if raw {
+                state.pressed_at = now;
+                state.long_reported = false;
+                push(ButtonEventKind::Pressed);
+
+                match state.last_click {
+                    Some(last) if now.duration_since(last) <= config.double_click => {
+                        state.last_click = None;
+                        push(ButtonEventKind::DoubleClick);
+                    }
+                    _ => state.last_click = Some(now),
+                }
+            } else {
+                push(ButtonEventKind::Released);
+            }
+        }
+
+        if state.pressed && !state.long_reported {
+            let held = now.duration_since(state.pressed_at);
+            if held >= config.long_press {
+                state.long_reported = true;
+                state.last_click = None;
+                push(ButtonEventKind::LongPress(held));
+            }
+        }
+    }
+
+    pub fn poll(&mut self) -> Option<ButtonEvent> {
+        self.update();
+        self.queue.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    // the debounced state
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.states[button as usize].pressed
+    }
+
+    pub fn held_for(&self, button: Button) -> Option<Duration> {
+        let state = &self.states[button as usize];
+        state.pressed.then(|| state.pressed_at.elapsed())
+    }
+
+    // blocks until a new press of `button`, dropping any pending events
+    pub fn wait_press(&mut self, button: Button) -> ButtonEvent {
+        self.clear();
+        loop {
+            match self.poll() {
+                Some(event) if event.button == button && event.kind == ButtonEventKind::Pressed => {
+                    return event
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Default for ButtonEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host;
+
+    fn events(buttons: &mut ButtonEvents) -> std::vec::Vec<ButtonEventKind> {
+        core::iter::from_fn(|| buttons.poll())
+            .map(|event| event.kind)
+            .collect()
+    }
+
+    // held long enough to pass the debounce
+    fn set(buttons: &mut ButtonEvents, pressed: bool) -> std::vec::Vec<ButtonEventKind> {
+        host::set_button(Button::Center, pressed);
+        buttons.update();
+        host::advance(Duration::from_millis(30));
+        events(buttons)
+    }
+
+    #[test]
+    fn debounce() {
+        host::reset();
+        let mut buttons = ButtonEvents::new();
+        host::set_button(Button::Center, true);
+        buttons.update();
+        host::set_button(Button::Center, false);
+        host::advance(Duration::from_millis(30));
+        assert!(events(&mut buttons).is_empty());
+        assert_eq!(set(&mut buttons, true), [ButtonEventKind::Pressed]);
+        assert_eq!(set(&mut buttons, false), [ButtonEventKind::Released]);
+    }
+
+    #[test]
+    fn double_click() {
+        host::reset();
+        let mut buttons = ButtonEvents::new();
+        set(&mut buttons, true);
+        set(&mut buttons, false);
+        assert_eq!(
+            set(&mut buttons, true),
+            [ButtonEventKind::Pressed, ButtonEventKind::DoubleClick]
+        );
+    }
+
+    #[test]
+    fn no_double_click_across_a_wrap() {
+        host::reset();
+        host::with_board(|board| board.cycles = (1 << 32) - 1_000_000);
+        let mut buttons = ButtonEvents::new();
+        set(&mut buttons, true);
+        set(&mut buttons, false);
+        // a second later, past the wrap
+        host::advance(Duration::from_secs(1));
+        assert_eq!(set(&mut buttons, true), [ButtonEventKind::Pressed]);
+    }
+
+    #[test]
+    fn long_press_across_a_wrap() {
+        host::reset();
+        host::with_board(|board| board.cycles = (1 << 32) - 1_000_000);
+        let mut buttons = ButtonEvents::new();
+        set(&mut buttons, true);
+        host::advance(Duration::from_millis(700));
+        assert!(events(&mut buttons).is_empty());
+        host::advance(Duration::from_millis(100));
+        match events(&mut buttons)[..] {
+            [ButtonEventKind::LongPress(held)] => {
+                assert!(held >= Duration::from_millis(800) && held < Duration::from_millis(900))
+            }
+            ref other => panic!("{:?}", other),
+        }
+        assert!(buttons.held_for(Button::Center).unwrap() >= Duration::from_millis(800));
+    }
+}
@@ -0,0 +1,67 @@
+// The code page of the font ROM (`font/font.txt`): printable ASCII at 0x20
+// to 0x7E, and custom glyphs from 0xA0. Every other slot is blank.
+
+// box drawing
+pub const VERTICAL: u8 = 0xA0;
+pub const HORIZONTAL: u8 = 0xA1;
+pub const DOWN_RIGHT: u8 = 0xA2;
+pub const DOWN_LEFT: u8 = 0xA3;
+pub const UP_RIGHT: u8 = 0xA4;
+pub const UP_LEFT: u8 = 0xA5;
+// for characters the font does not have
+pub const REPLACEMENT: u8 = 0xA6;
+
+const CUSTOM: [(u8, char); 7] = [
+    (VERTICAL, '│'),
+    (HORIZONTAL, '─'),
+    (DOWN_RIGHT, '┌'),
+    (DOWN_LEFT, '┐'),
+    (UP_RIGHT, '└'),
+    (UP_LEFT, '┘'),
+    (REPLACEMENT, char::REPLACEMENT_CHARACTER),
+];
+
+pub fn is_printable(glyph: u8) -> bool {
+    matches!(glyph, 0x20..=0x7E) || CUSTOM.iter().any(|(g, _)| *g == glyph)
+}
+
+// ASCII, control characters included, stays as it is
+pub fn from_char(c: char) -> u8 {
+    match c {
+        '\0'..='\x7f' => c as u8,
+        // the heavy, double and rounded lines look the same
+        '│' | '┃' | '║' => VERTICAL,
+        '─' | '━' | '═' => HORIZONTAL,
+        '┌' | '┏' | '╔' | '╭' => DOWN_RIGHT,
+        '┐' | '┓' | '╗' | '╮' => DOWN_LEFT,
+        '└' | '┗' | '╚' | '╰' => UP_RIGHT,
+        '┘' | '┛' | '╝' | '╯' => UP_LEFT,
+        _ => REPLACEMENT,
+    }
+}
+
+// `None` for the blank slots
+pub fn to_char(glyph: u8) -> Option<char> {
+    match glyph {
+        0x20..=0x7E => Some(glyph as char),
+        _ => CUSTOM.iter().find(|(g, _)| *g == glyph).map(|(_, c)| *c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for glyph in 0..=255 {
+            if let Some(c) = to_char(glyph) {
+                assert_eq!(from_char(c), glyph);
+            }
+        }
+        assert_eq!(from_char('╔'), from_char('┌'));
+        assert_eq!(from_char('é'), REPLACEMENT);
+        assert_eq!(from_char(char::REPLACEMENT_CHARACTER), REPLACEMENT);
+        assert_eq!(to_char(0x80), None);
+    }
+}
@@ -1,42 +1,188 @@
 use core::fmt::{self, Write};
+use core::ops::BitOr;
+use core::ptr::addr_of_mut;
 
-use crate::monitor;
+use crate::ansi::Attributes;
+use crate::codepage;
+use crate::keys::KeyDecoder;
+use crate::line_editor::{History, LineEditor};
+use crate::monitor::{self, Color};
+use crate::ring::RingBuffer;
+use crate::time::{Deadline, TimedOut};
 use crate::uart;
+use crate::utf8::{Utf8Decoder, Utf8Error};
 
-struct Stdout;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+// where a stream goes, combined with `|`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sinks(u8);
+
+impl Sinks {
+    pub const NONE: Sinks = Sinks(0);
+    pub const UART: Sinks = Sinks(1 << 0);
+    pub const SCREEN: Sinks = Sinks(1 << 1);
+    // kept in memory, see `read_ring`
+    pub const RING: Sinks = Sinks(1 << 2);
+
+    pub const fn union(self, other: Sinks) -> Sinks {
+        Sinks(self.0 | other.0)
+    }
+
+    pub const fn difference(self, other: Sinks) -> Sinks {
+        Sinks(self.0 & !other.0)
+    }
+
+    pub const fn contains(self, other: Sinks) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for Sinks {
+    type Output = Sinks;
+
+    fn bitor(self, rhs: Sinks) -> Sinks {
+        self.union(rhs)
+    }
+}
+
+pub const RING_SIZE: usize = 1024;
 
 const UART_NEWLINE: u8 = b'\r';
 const UART_BACKSPACE: u8 = b'\x7f';
 
-static mut PRINT_TO_SCREEN: bool = true;
+// indexed by `Stream`
+static mut SINKS: [Sinks; 2] = [Sinks::UART.union(Sinks::SCREEN); 2];
+static mut STDERR_COLOR: u8 = (Color::Black as u8) << 4 | Color::BrightRed as u8;
+// the oldest output is dropped when full
+static mut RING: RingBuffer<u8, RING_SIZE> = RingBuffer::new();
+static mut HISTORY: History = History::new();
+// kept across lines, a CR LF can end one line and the next read gets the LF
+static mut KEYS: KeyDecoder = KeyDecoder::new();
+static mut UTF8: Utf8Decoder = Utf8Decoder::new();
+// a byte that cut a character short, read again by the next `read_char`
+static mut UNREAD: Option<u8> = None;
 
-fn putchar(ch: u8) {
-    uart::write(ch);
-    if unsafe { PRINT_TO_SCREEN } {
-        monitor::monitor::putchar(ch);
-    }
+pub fn sinks(stream: Stream) -> Sinks {
+    unsafe { SINKS[stream as usize] }
 }
 
+pub fn set_sinks(stream: Stream, sinks: Sinks) {
+    unsafe { SINKS[stream as usize] = sinks }
+}
+
+// only for stdout, kept for older programs
 pub fn set_screen_print(enable: bool) {
-    unsafe { PRINT_TO_SCREEN = enable }
+    let sinks = sinks(Stream::Stdout);
+    if enable {
+        set_sinks(Stream::Stdout, sinks | Sinks::SCREEN);
+    } else {
+        set_sinks(Stream::Stdout, sinks.difference(Sinks::SCREEN));
+    }
 }
 
-impl Write for Stdout {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            putchar(c as u8);
+// the color stderr starts with on the screen
+pub fn set_stderr_color(color: u8) {
+    unsafe { STDERR_COLOR = color }
+}
+
+// moves the oldest output of `Sinks::RING` into `buf`, returns the length
+pub fn read_ring(buf: &mut [u8]) -> usize {
+    for (len, slot) in buf.iter_mut().enumerate() {
+        match unsafe { (*addr_of_mut!(RING)).pop() } {
+            Some(ch) => *slot = ch,
+            None => return len,
         }
+    }
+    buf.len()
+}
+
+pub fn clear_ring() {
+    unsafe { (*addr_of_mut!(RING)).clear() }
+}
+
+fn write_bytes(sinks: Sinks, bytes: impl Iterator<Item = u8> + Clone) {
+    if sinks.contains(Sinks::UART) {
+        bytes.clone().for_each(uart::write);
+        // nothing is left queued, in case what follows never returns
+        uart::flush();
+    }
+    if sinks.contains(Sinks::RING) {
+        bytes.for_each(|ch| unsafe {
+            (*addr_of_mut!(RING)).push_overwrite(ch);
+        });
+    }
+}
+
+// `bytes` go to the UART and the ring, `glyphs` to the screen
+fn write_to_sinks(
+    stream: Stream,
+    bytes: impl Iterator<Item = u8> + Clone,
+    glyphs: impl Iterator<Item = u8>,
+) {
+    let sinks = sinks(stream);
+    write_bytes(sinks, bytes);
+    if sinks.contains(Sinks::SCREEN) {
+        glyphs.for_each(monitor::monitor::putchar);
+        monitor::flush();
+    }
+}
+
+// the echo of `LineEditor`, which draws on the screen itself
+pub(crate) fn echo_bytes(data: &[u8]) {
+    write_bytes(sinks(Stream::Stdout), data.iter().copied());
+}
+
+fn putchar(ch: u8) {
+    write_to_sinks(Stream::Stdout, core::iter::once(ch), core::iter::once(ch));
+}
+
+struct Writer(Stream);
+
+impl Write for Writer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_to_sinks(self.0, s.bytes(), s.chars().map(codepage::from_char));
         Ok(())
     }
 }
 
 pub fn print_str(s: &str) {
-    let result = Stdout.write_str(s);
+    let result = Writer(Stream::Stdout).write_str(s);
     unsafe { result.unwrap_unchecked() }
 }
 
 pub fn print(args: fmt::Arguments) {
-    Stdout.write_fmt(args).unwrap();
+    Writer(Stream::Stdout).write_fmt(args).unwrap();
+}
+
+// On the screen, stderr starts out in `STDERR_COLOR` and escape sequences
+// in the text change it from there. Stdout's attributes are back afterwards.
+fn with_stderr_color(write: impl FnOnce()) {
+    if !sinks(Stream::Stderr).contains(Sinks::SCREEN) {
+        return write();
+    }
+    let saved = monitor::monitor::get_attributes();
+    monitor::monitor::set_attributes(Attributes {
+        color: unsafe { STDERR_COLOR },
+        bold: false,
+    });
+    write();
+    monitor::monitor::set_attributes(saved);
+}
+
+pub fn eprint_str(s: &str) {
+    with_stderr_color(|| {
+        let result = Writer(Stream::Stderr).write_str(s);
+        unsafe { result.unwrap_unchecked() }
+    });
+}
+
+pub fn eprint(args: fmt::Arguments) {
+    with_stderr_color(|| Writer(Stream::Stderr).write_fmt(args).unwrap());
 }
 
 #[macro_export]
@@ -53,42 +199,166 @@ macro_rules! println {
     }
 }
 
+#[macro_export]
+macro_rules! eprint {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        $crate::prelude::eprint(format_args!($fmt $(, $($arg)+)?))
+    }
+}
+
+#[macro_export]
+macro_rules! eprintln {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        $crate::prelude::eprint(format_args!(concat!($fmt, "\r\n") $(, $($arg)+)?))
+    }
+}
+
 pub fn get_char(echo: bool) -> u8 {
     let c = uart::read();
     if echo {
-        putchar(c);
-        match c {
-            UART_NEWLINE => putchar(b'\n'),
-            UART_BACKSPACE => {
-                putchar(b'\x08');
-                putchar(b' ');
-                putchar(b'\x08');
+        echo_char(c);
+    }
+    c
+}
+
+pub fn get_char_timeout(echo: bool, deadline: impl Into<Deadline>) -> Result<u8, TimedOut> {
+    let c = uart::read_timeout(deadline)?;
+    if echo {
+        echo_char(c);
+    }
+    Ok(c)
+}
+
+// a whole character, `char::REPLACEMENT_CHARACTER` for broken UTF-8
+pub fn read_char(echo: bool) -> char {
+    let c = loop {
+        let byte = unsafe { (*addr_of_mut!(UNREAD)).take() }.unwrap_or_else(uart::read);
+        match unsafe { (*addr_of_mut!(UTF8)).push(byte) } {
+            None => {}
+            Some(Ok(c)) => break c,
+            Some(Err(Utf8Error::Interrupted)) => {
+                unsafe { UNREAD = Some(byte) }
+                break char::REPLACEMENT_CHARACTER;
             }
-            _ => {}
+            Some(Err(Utf8Error::Invalid)) => break char::REPLACEMENT_CHARACTER,
+        }
+    };
+    if echo {
+        if c.is_ascii() {
+            echo_char(c as u8);
+        } else {
+            print_str(c.encode_utf8(&mut [0; 4]));
         }
     }
     c
 }
 
+fn echo_char(c: u8) {
+    match c {
+        UART_NEWLINE => print_str("\r\n"),
+        // rub out the last character
+        UART_BACKSPACE => print_str("\x08 \x08"),
+        c => putchar(c),
+    }
+}
+
 pub fn read_line(buf: &mut [u8]) -> &str {
-    let mut idx: usize = 0;
+    // cannot time out
+    unsafe { read_line_timeout(buf, Deadline::NEVER).unwrap_unchecked() }
+}
 
-    while idx < buf.len() {
-        match get_char(true) {
-            UART_NEWLINE => break,
-            UART_BACKSPACE => {
-                idx = idx.saturating_sub(1);
-            }
-            c => {
-                buf[idx] = c;
-                idx += 1;
+// the deadline is for the whole line, see `line_editor` for the keys
+pub fn read_line_timeout(buf: &mut [u8], deadline: impl Into<Deadline>) -> Result<&str, TimedOut> {
+    let deadline = deadline.into();
+    let history = unsafe { &mut *addr_of_mut!(HISTORY) };
+    let screen = sinks(Stream::Stdout).contains(Sinks::SCREEN);
+    let mut editor = LineEditor::new(buf, Some(history), screen);
+
+    let keys = unsafe { &mut *addr_of_mut!(KEYS) };
+    loop {
+        if let Some(key) = keys.push(uart::read_timeout(deadline)?) {
+            if editor.push_key(key) {
+                return Ok(editor.finish());
             }
         }
     }
+}
 
-    if idx > buf.len() {
-        unsafe { core::hint::unreachable_unchecked() }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{self, push_uart_input, take_uart_output};
+
+    fn fresh() {
+        host::reset();
+        uart::discard_input();
+        unsafe { (*addr_of_mut!(KEYS)).reset() };
+        clear_ring();
+    }
+
+    #[test]
+    fn line_echo_follows_stdout() {
+        fresh();
+        set_sinks(Stream::Stdout, Sinks::UART);
+        push_uart_input(b"ab\x7fc\n");
+        let mut buf = [0; 16];
+        assert_eq!(read_line(&mut buf), "ac");
+        assert_eq!(take_uart_output(), b"ab\x1b[1D\x1b[Kc\r\n");
+
+        set_sinks(Stream::Stdout, Sinks::RING);
+        push_uart_input(b"xy\r");
+        assert_eq!(read_line(&mut buf), "xy");
+        assert!(take_uart_output().is_empty());
+        let mut ring = [0; 32];
+        let len = read_ring(&mut ring);
+        assert_eq!(&ring[..len], b"xy\r\n");
+
+        set_sinks(Stream::Stdout, Sinks::UART | Sinks::SCREEN);
     }
 
-    unsafe { core::str::from_utf8_unchecked(&buf[..idx]) }
+    #[test]
+    fn crlf_lines() {
+        fresh();
+        push_uart_input(b"a\r\nb\r\n");
+        let mut buf = [0; 16];
+        assert_eq!(read_line(&mut buf), "a");
+        assert_eq!(read_line(&mut buf), "b");
+        // the LF left over is not an empty line
+        push_uart_input(b"c\r");
+        assert_eq!(read_line(&mut buf), "c");
+    }
+
+    #[test]
+    fn stderr_colors() {
+        fresh();
+        let stdout = monitor::monitor::get_attributes();
+        let stderr = unsafe { STDERR_COLOR };
+        let yellow = {
+            let mut attributes = Attributes {
+                color: stderr,
+                bold: false,
+            };
+            attributes.apply(&[33]);
+            attributes.color
+        };
+
+        monitor::monitor::set_xy(0, 0);
+        eprint_str("a");
+        // split like the logger's output
+        eprint(format_args!("{}{}{} {}", "\x1b[33m", "W", "\x1b[0m", "b"));
+        print_str("c");
+
+        let colors = [0, 1, 2, 3, 4].map(|x| host::screen_color(x, 0));
+        assert_eq!(
+            colors,
+            [
+                stderr,
+                yellow,
+                Attributes::DEFAULT.color,
+                Attributes::DEFAULT.color,
+                stdout.color
+            ]
+        );
+        assert_eq!(monitor::monitor::get_attributes(), stdout);
+    }
 }
@@ -0,0 +1,209 @@
+// A copy of the visible screen in RAM. Drawing only touches the copy and
+// marks the cells that changed, `flush` then writes just those to the VGA
+// buffers. Once installed with `monitor::install_shadow`, the `monitor`
+// functions go through it.
+
+use crate::monitor::{self, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::platform::TextDisplay;
+
+const CELLS: usize = SCREEN_WIDTH * SCREEN_HEIGHT;
+const DIRTY_WORDS: usize = CELLS.div_ceil(32);
+
+pub struct Framebuffer {
+    chars: [u8; CELLS],
+    colors: [u8; CELLS],
+    // a bit per cell
+    dirty: [u32; DIRTY_WORDS],
+}
+
+#[inline]
+fn index(x: usize, y: usize) -> usize {
+    assert!(x < SCREEN_WIDTH && y < SCREEN_HEIGHT);
+    y * SCREEN_WIDTH + x
+}
+
+impl Framebuffer {
+    pub const fn new() -> Framebuffer {
+        Framebuffer {
+            chars: [0; CELLS],
+            colors: [0; CELLS],
+            dirty: [0; DIRTY_WORDS],
+        }
+    }
+
+    #[inline]
+    fn mark(&mut self, i: usize) {
+        self.dirty[i / 32] |= 1 << (i % 32);
+    }
+
+    pub fn set_char(&mut self, x: usize, y: usize, ch: u8) {
+        let i = index(x, y);
+        if self.chars[i] != ch {
+            self.chars[i] = ch;
+            self.mark(i);
+        }
+    }
+
+    pub fn char(&self, x: usize, y: usize) -> u8 {
+        self.chars[index(x, y)]
+    }
+
+    pub fn set_color(&mut self, x: usize, y: usize, color: u8) {
+        let i = index(x, y);
+        if self.colors[i] != color {
+            self.colors[i] = color;
+            self.mark(i);
+        }
+    }
+
+    pub fn color(&self, x: usize, y: usize) -> u8 {
+        self.colors[index(x, y)]
+    }
+
+    // moves everything up a line, the new bottom line is blank but keeps its colors
+    pub fn scroll_up(&mut self) {
+        for i in 0..CELLS - SCREEN_WIDTH {
+            let (ch, color) = (self.chars[i + SCREEN_WIDTH], self.colors[i + SCREEN_WIDTH]);
+            if self.chars[i] != ch || self.colors[i] != color {
+                self.chars[i] = ch;
+                self.colors[i] = color;
+                self.mark(i);
+            }
+        }
+        for x in 0..SCREEN_WIDTH {
+            self.set_char(x, SCREEN_HEIGHT - 1, b' ');
+        }
+    }
+
+    pub fn dirty_cells(&self) -> usize {
+        self.dirty.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    // the next flush rewrites the whole screen
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty = [u32::MAX; DIRTY_WORDS];
+    }
+
+    // takes over what the screen shows now
+    pub fn load(&mut self) {
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                let i = index(x, y);
+                self.chars[i] = monitor::vga::get_character(x, y);
+                self.colors[i] = monitor::vga::get_color(x, y);
+            }
+        }
+        self.dirty = [0; DIRTY_WORDS];
+    }
+
+    // writes the changed cells to the screen, returns how many
+    pub fn flush(&mut self) -> usize {
+        let mut written = 0;
+        for word in 0..DIRTY_WORDS {
+            let mut bits = self.dirty[word];
+            self.dirty[word] = 0;
+            while bits != 0 {
+                let i = word * 32 + bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                if i >= CELLS {
+                    break;
+                }
+                let (x, y) = (i % SCREEN_WIDTH, i / SCREEN_WIDTH);
+                monitor::vga::set_character(x, y, self.chars[i]);
+                monitor::vga::set_color(x, y, self.colors[i]);
+                written += 1;
+            }
+        }
+        written
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextDisplay for Framebuffer {
+    fn size(&self) -> (usize, usize) {
+        (SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, ch: u8, color: u8) {
+        self.set_char(x, y, ch);
+        self.set_color(x, y, color);
+    }
+
+    fn cell(&self, x: usize, y: usize) -> (u8, u8) {
+        (self.char(x, y), self.color(x, y))
+    }
+
+    fn flush(&mut self) {
+        Framebuffer::flush(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{self, screen_char, screen_color};
+    use crate::monitor::vga;
+
+    // a blank screen, and a framebuffer that holds it
+    fn fresh() -> Framebuffer {
+        host::reset();
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                vga::set_character(x, y, b' ');
+            }
+        }
+        let mut framebuffer = Framebuffer::new();
+        framebuffer.load();
+        framebuffer
+    }
+
+    #[test]
+    fn load_clears_dirty() {
+        let mut framebuffer = fresh();
+        vga::set_character(5, 5, b'z');
+        framebuffer.mark_all_dirty();
+        framebuffer.load();
+        assert_eq!(framebuffer.dirty_cells(), 0);
+        assert_eq!(framebuffer.char(5, 5), b'z');
+    }
+
+    #[test]
+    fn flushes_changes_only() {
+        let mut framebuffer = fresh();
+        framebuffer.set_char(1, 1, b'a');
+        framebuffer.set_color(1, 1, 0x1F);
+        framebuffer.set_char(2, 1, b'b');
+        // the same as before
+        framebuffer.set_char(3, 1, b' ');
+        framebuffer.set_color(3, 1, 0);
+        assert_eq!(framebuffer.dirty_cells(), 2);
+
+        // a flush of every cell would overwrite it
+        vga::set_character(7, 7, b'q');
+        assert_eq!(framebuffer.flush(), 2);
+        assert_eq!(screen_char(1, 1), b'a');
+        assert_eq!(screen_color(1, 1), 0x1F);
+        assert_eq!(screen_char(2, 1), b'b');
+        assert_eq!(screen_char(7, 7), b'q');
+        assert_eq!(framebuffer.dirty_cells(), 0);
+        assert_eq!(framebuffer.flush(), 0);
+    }
+
+    #[test]
+    fn scroll_marks_changes_only() {
+        let mut framebuffer = fresh();
+        framebuffer.set_char(0, 1, b'a');
+        framebuffer.flush();
+        framebuffer.scroll_up();
+        // `a` moved up, and left a blank behind
+        assert_eq!(framebuffer.dirty_cells(), 2);
+        assert_eq!(framebuffer.flush(), 2);
+        assert_eq!(screen_char(0, 0), b'a');
+        assert_eq!(screen_char(0, 1), b' ');
+    }
+}
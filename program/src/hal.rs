@@ -0,0 +1,236 @@
+// `embedded-hal` and `embedded-io` for the board, so that drivers and
+// utilities written against them work here.
+
+use core::convert::Infallible;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{self, InputPin, OutputPin, StatefulOutputPin};
+use embedded_io::{self as io, Read, ReadReady, Write, WriteReady};
+
+use crate::board::{self, Button, LED_COUNT, SWITCH_COUNT};
+use crate::time::{Ticks, CYCLES_PER_MICRO};
+use crate::uart::{self, Uart, TX_BUFFER_SIZE};
+
+impl io::ErrorType for Uart {
+    type Error = Infallible;
+}
+
+impl Read for Uart {
+    // blocks until at least one byte arrives
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = uart::read();
+        let mut len = 1;
+        while len < buf.len() {
+            match uart::try_read() {
+                Some(data) => buf[len] = data,
+                None => break,
+            }
+            len += 1;
+        }
+        Ok(len)
+    }
+}
+
+impl ReadReady for Uart {
+    fn read_ready(&mut self) -> Result<bool, Infallible> {
+        Ok(uart::input_ready())
+    }
+}
+
+impl Write for Uart {
+    // blocks until at least one byte is queued
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        uart::write(buf[0]);
+        // stops at a full queue, which is not an overflow
+        let len = 1 + buf[1..]
+            .iter()
+            .take_while(|_| uart::tx_pending() < TX_BUFFER_SIZE)
+            .inspect(|b| uart::write(**b))
+            .count();
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> Result<(), Infallible> {
+        uart::flush();
+        Ok(())
+    }
+}
+
+impl WriteReady for Uart {
+    fn write_ready(&mut self) -> Result<bool, Infallible> {
+        uart::poll();
+        Ok(uart::tx_pending() < TX_BUFFER_SIZE)
+    }
+}
+
+// busy-waits on the cycle counter, at least as long as asked
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Delay;
+
+// `delay_ms(u32::MAX)` is longer than a wrap of the counter, so the cycles
+// are counted one reading at a time
+fn wait_cycles(mut cycles: u64) {
+    let mut last = Ticks::now();
+    while cycles > 0 {
+        let now = Ticks::now();
+        cycles = cycles.saturating_sub(now.cycles_since(last) as u64);
+        last = now;
+    }
+}
+
+impl DelayNs for Delay {
+    fn delay_ns(&mut self, ns: u32) {
+        wait_cycles((ns as u64 * CYCLES_PER_MICRO as u64).div_ceil(1000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        wait_cycles(us as u64 * CYCLES_PER_MICRO as u64);
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        wait_cycles(ms as u64 * 1000 * CYCLES_PER_MICRO as u64);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Led(usize);
+
+impl Led {
+    pub fn new(index: usize) -> Led {
+        assert!(index < LED_COUNT);
+        Led(index)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl digital::ErrorType for Led {
+    type Error = Infallible;
+}
+
+impl OutputPin for Led {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        board::set_led(self.0, false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        board::set_led(self.0, true);
+        Ok(())
+    }
+}
+
+// the state as last written, since the LEDs cannot be read back
+impl StatefulOutputPin for Led {
+    fn is_set_high(&mut self) -> Result<bool, Infallible> {
+        Ok(board::read_leds() & (1 << self.0) != 0)
+    }
+
+    fn is_set_low(&mut self) -> Result<bool, Infallible> {
+        self.is_set_high().map(|high| !high)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Switch(usize);
+
+impl Switch {
+    pub fn new(index: usize) -> Switch {
+        assert!(index < SWITCH_COUNT);
+        Switch(index)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl digital::ErrorType for Switch {
+    type Error = Infallible;
+}
+
+impl InputPin for Switch {
+    fn is_high(&mut self) -> Result<bool, Infallible> {
+        Ok(board::read_switch(self.0))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Infallible> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+// high while pressed, not debounced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonPin(pub Button);
+
+impl digital::ErrorType for ButtonPin {
+    type Error = Infallible;
+}
+
+impl InputPin for ButtonPin {
+    fn is_high(&mut self) -> Result<bool, Infallible> {
+        Ok(board::read_button(self.0))
+    }
+
+    fn is_low(&mut self) -> Result<bool, Infallible> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{self, set_cycles_per_read, take_uart_output, with_board};
+
+    fn waited(delay: impl FnOnce(&mut Delay)) -> u64 {
+        let start = with_board(|b| b.cycles);
+        delay(&mut Delay);
+        with_board(|b| b.cycles) - start
+    }
+
+    #[test]
+    fn rounds_up() {
+        host::reset();
+        set_cycles_per_read(1);
+        // a cycle is 100 ns
+        assert!(waited(|d| d.delay_ns(1)) >= 1);
+        assert!(waited(|d| d.delay_ns(101)) >= 2);
+        assert!(waited(|d| d.delay_us(3)) >= 30);
+    }
+
+    #[test]
+    fn longer_than_a_wrap() {
+        host::reset();
+        set_cycles_per_read(1 << 20);
+        with_board(|b| b.cycles = u32::MAX as u64 - 5000);
+        let cycles = waited(|d| d.delay_ms(500_000));
+        assert!(cycles >= 5_000_000_000);
+        assert!(cycles < 5_000_000_000 + (3 << 20));
+    }
+
+    #[test]
+    fn write_stops_at_a_full_queue() {
+        host::reset();
+        uart::flush();
+        uart::reset_stats();
+        with_board(|b| b.uart_tx_ready = false);
+        assert_eq!(Uart.write(&[b'a'; 300]), Ok(TX_BUFFER_SIZE));
+        assert_eq!(uart::stats().tx_overflows, 0);
+
+        with_board(|b| b.uart_tx_ready = true);
+        Uart.write_all(&[b'b'; 1000]).unwrap();
+        Uart.flush().unwrap();
+        assert_eq!(uart::stats().tx_overflows, 0);
+        let output = take_uart_output();
+        assert_eq!(output.len(), TX_BUFFER_SIZE + 1000);
+        assert!(output[TX_BUFFER_SIZE..].iter().all(|b| *b == b'b'));
+    }
+}
@@ -0,0 +1,352 @@
+// Host backend: emulates the board in memory, so that the library can be
+// built and unit-tested on the development machine (`make test`).
+//
+// Every thread has its own board. Note that the drivers still keep some
+// global state (e.g. the screen cursor and the LED shadow), so tests that
+// go through them should run single-threaded.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::string::String;
+use std::vec::Vec;
+
+use core::time::Duration;
+
+use crate::board::{Button, Keyboard};
+use crate::codepage;
+use crate::mmio::{self, VGA_BUFFER_WIDTH};
+use crate::time::CYCLES_PER_MICRO;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TubeState {
+    pub mode: u32,
+    pub value: u32,
+    pub enable: u32,
+}
+
+pub struct HostBoard {
+    pub uart_input: VecDeque<u8>,
+    pub uart_output: Vec<u8>,
+    // `false` stalls the UART output, as if the line was busy
+    pub uart_tx_ready: bool,
+    pub keyboard_input: VecDeque<u8>,
+    pub buttons: [bool; 5],
+    pub switches: u32,
+    pub leds: u32,
+    pub tube: TubeState,
+    pub interrupt_pc: u32,
+    pub random_state: u32,
+    // virtual clock, advanced by every read of the cycle counter
+    pub cycles: u64,
+    pub cycles_per_read: u64,
+    pub chars: Vec<u8>,
+    pub colors: Vec<u8>,
+    // everything else, word-addressed
+    pub memory: BTreeMap<usize, u32>,
+    // like the hardware, reading an empty UART or keypad gives the last dequeued value
+    dequeued: u32,
+}
+
+impl HostBoard {
+    pub fn new() -> HostBoard {
+        let cells = mmio::VGA_CHARS.len();
+        HostBoard {
+            uart_input: VecDeque::new(),
+            uart_output: Vec::new(),
+            uart_tx_ready: true,
+            keyboard_input: VecDeque::new(),
+            buttons: [false; 5],
+            switches: 0,
+            leds: 0,
+            tube: TubeState::default(),
+            interrupt_pc: 0,
+            random_state: 0x2545F491,
+            cycles: 0,
+            cycles_per_read: 100,
+            chars: std::vec![0; cells],
+            colors: std::vec![0; cells],
+            memory: BTreeMap::new(),
+            dequeued: 0,
+        }
+    }
+
+    fn next_random(&mut self) -> u32 {
+        // xorshift32
+        let mut x = self.random_state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.random_state = x;
+        x
+    }
+
+    pub fn read(&mut self, addr: usize) -> u32 {
+        match addr {
+            0xFFFFF000 => {
+                if let Some(data) = self.uart_input.pop_front() {
+                    self.dequeued = data as u32;
+                }
+                self.dequeued
+            }
+            0xFFFFF008 => !self.uart_input.is_empty() as u32,
+            0xFFFFF00C => self.uart_tx_ready as u32,
+            0xFFFFF010 => {
+                self.cycles += self.cycles_per_read;
+                self.cycles as u32
+            }
+            0xFFFFF014 => {
+                if let Some(code) = self.keyboard_input.pop_front() {
+                    self.dequeued = code as u32;
+                }
+                self.dequeued
+            }
+            0xFFFFF018 => !self.keyboard_input.is_empty() as u32,
+            0xFFFFF01C..=0xFFFFF02C => self.buttons[(addr - 0xFFFFF01C) / 4] as u32,
+            0xFFFFF040 => self.interrupt_pc,
+            0xFFFFF044 => self.next_random(),
+            0xFFFFF100..=0xFFFFF15C => (self.switches >> ((addr - 0xFFFFF100) / 4)) & 1,
+            0xFFFD0000..=0xFFFDFFFF => self.chars[(addr & 0xFFFF) / 4] as u32,
+            0xFFFE0000..=0xFFFEFFFF => self.colors[(addr & 0xFFFF) / 4] as u32,
+            // the rest of the MMIO page
+            addr if addr >= 0xFFFFF000 => 0,
+            addr => self.memory.get(&(addr & !3)).copied().unwrap_or(0),
+        }
+    }
+
+    pub fn write(&mut self, addr: usize, data: u32) {
+        match addr {
+            0xFFFFF004 => self.uart_output.push(data as u8),
+            0xFFFFF030 => self.tube.mode = data & 1,
+            0xFFFFF038 => self.tube.value = data,
+            0xFFFFF03C => self.tube.enable = data & 0xFF,
+            0xFFFFF200..=0xFFFFF25C => {
+                let bit = 1 << ((addr - 0xFFFFF200) / 4);
+                self.leds = self.leds & !bit | if data & 1 != 0 { bit } else { 0 };
+            }
+            0xFFFD0000..=0xFFFDFFFF => self.chars[(addr & 0xFFFF) / 4] = data as u8,
+            0xFFFE0000..=0xFFFEFFFF => self.colors[(addr & 0xFFFF) / 4] = data as u8,
+            addr if addr >= 0xFFFFF000 => {}
+            addr => {
+                self.memory.insert(addr & !3, data);
+            }
+        }
+    }
+}
+
+impl Default for HostBoard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+std::thread_local! {
+    static BOARD: RefCell<HostBoard> = RefCell::new(HostBoard::new());
+}
+
+pub fn with_board<R>(f: impl FnOnce(&mut HostBoard) -> R) -> R {
+    BOARD.with(|board| f(&mut board.borrow_mut()))
+}
+
+pub(crate) fn read(addr: usize) -> u32 {
+    with_board(|board| board.read(addr))
+}
+
+pub(crate) fn write(addr: usize, data: u32) {
+    with_board(|board| board.write(addr, data))
+}
+
+// replaces the board of this thread with a fresh one
+pub fn reset() {
+    with_board(|board| *board = HostBoard::new());
+}
+
+pub fn push_uart_input(data: &[u8]) {
+    with_board(|board| board.uart_input.extend(data));
+}
+
+pub fn take_uart_output() -> Vec<u8> {
+    with_board(|board| core::mem::take(&mut board.uart_output))
+}
+
+pub fn push_key(key: Keyboard) {
+    push_key_code(key as u8);
+}
+
+pub fn push_key_code(code: u8) {
+    with_board(|board| board.keyboard_input.push_back(code));
+}
+
+pub fn set_button(button: Button, pressed: bool) {
+    with_board(|board| board.buttons[button as usize] = pressed);
+}
+
+pub fn set_switches(switches: u32) {
+    with_board(|board| board.switches = switches);
+}
+
+pub fn set_switch(index: usize, on: bool) {
+    with_board(|board| {
+        board.switches = board.switches & !(1 << index) | (on as u32) << index;
+    });
+}
+
+pub fn leds() -> u32 {
+    with_board(|board| board.leds)
+}
+
+pub fn tube() -> TubeState {
+    with_board(|board| board.tube)
+}
+
+pub fn set_interrupt_pc(pc: u32) {
+    with_board(|board| board.interrupt_pc = pc);
+}
+
+pub fn set_cycles_per_read(cycles: u64) {
+    with_board(|board| board.cycles_per_read = cycles);
+}
+
+pub fn advance_cycles(cycles: u64) {
+    with_board(|board| board.cycles += cycles);
+}
+
+pub fn advance(duration: Duration) {
+    advance_cycles(duration.as_micros() as u64 * CYCLES_PER_MICRO as u64);
+}
+
+pub fn screen_char(x: usize, y: usize) -> u8 {
+    with_board(|board| board.chars[y * VGA_BUFFER_WIDTH + x])
+}
+
+pub fn screen_color(x: usize, y: usize) -> u8 {
+    with_board(|board| board.colors[y * VGA_BUFFER_WIDTH + x])
+}
+
+// the visible part of row `y`, with trailing blanks removed
+pub fn screen_line(y: usize) -> String {
+    let line: String = (0..crate::monitor::SCREEN_WIDTH)
+        .map(|x| codepage::to_char(screen_char(x, y)).unwrap_or(' '))
+        .collect();
+    String::from(line.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::{self, InvalidKeyCode, SwitchWatcher, LED_COUNT};
+    use crate::time::Instant;
+    use crate::tube::{self, TubeMode};
+    use crate::{monitor, uart};
+
+    // a new board, and driver state that matches it
+    fn fresh() {
+        reset();
+        uart::discard_input();
+        (0..LED_COUNT).for_each(|i| board::set_led(i, false));
+    }
+
+    #[test]
+    fn keyboard() {
+        fresh();
+        push_key(Keyboard::A);
+        push_key_code(0x1F);
+        push_key(Keyboard::_7);
+        assert_eq!(board::try_read_keyboard(), Some(Ok(Keyboard::A)));
+        assert_eq!(board::try_read_keyboard(), Some(Err(InvalidKeyCode(0x1F))));
+        assert_eq!(board::read_keyboard(), Keyboard::_7);
+        assert_eq!(board::try_read_keyboard(), None);
+    }
+
+    #[test]
+    fn switches() {
+        fresh();
+        set_switches(0b1000_0000_0000_0000_0101);
+        assert!(board::read_switch(0));
+        assert!(!board::read_switch(1));
+        assert_eq!(board::read_switches(), 0b1000_0000_0000_0000_0101);
+        assert_eq!(board::switches(0..3), 0b101);
+        assert_eq!(board::switches(16..24), 0b1000);
+
+        let mut watcher = SwitchWatcher::new();
+        assert!(watcher.poll().is_empty());
+        set_switch(0, false);
+        set_switch(23, true);
+        let changes = watcher.poll();
+        assert_eq!(changes.rising(), 1 << 23);
+        assert_eq!(changes.falling(), 1);
+        assert_eq!(changes.iter().collect::<Vec<_>>(), [0, 23]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn switches_wider_than_a_byte() {
+        fresh();
+        board::switches(0..9);
+    }
+
+    #[test]
+    fn leds() {
+        fresh();
+        board::set_led(3, true);
+        assert_eq!(super::leds(), 1 << 3);
+        board::write_leds(0b101);
+        assert_eq!(super::leds(), 0b101);
+        assert_eq!(board::read_leds(), 0b101);
+
+        let bank = board::leds(8..12);
+        bank.set(0b1001);
+        assert_eq!(super::leds(), 0b1001 << 8 | 0b101);
+        assert_eq!(bank.get(), 0b1001);
+        bank.clear();
+        assert_eq!(super::leds(), 0b101);
+    }
+
+    #[test]
+    fn uart_queues() {
+        fresh();
+        push_uart_input(b"hi");
+        assert_eq!(uart::rx_pending(), 2);
+        assert_eq!(uart::read(), b'h');
+        assert_eq!(uart::try_read(), Some(b'i'));
+        assert_eq!(uart::try_read(), None);
+
+        b"ok".iter().for_each(|b| uart::write(*b));
+        uart::flush();
+        assert_eq!(take_uart_output(), b"ok");
+        assert!(take_uart_output().is_empty());
+    }
+
+    #[test]
+    fn buttons_and_tube() {
+        fresh();
+        set_button(Button::Up, true);
+        assert!(board::read_button(Button::Up));
+        assert!(!board::read_button(Button::Down));
+
+        tube::set_tube_mode(TubeMode::Hex);
+        tube::set_tube_value(0xBEEF);
+        tube::set_tube_enable(0x0F);
+        assert_eq!(
+            tube(),
+            TubeState {
+                mode: 1,
+                value: 0xBEEF,
+                enable: 0x0F
+            }
+        );
+    }
+
+    #[test]
+    fn screen_and_clock() {
+        fresh();
+        monitor::set_character(1, 2, b'A');
+        monitor::set_color(1, 2, 0x1F);
+        assert_eq!(screen_char(1, 2), b'A');
+        assert_eq!(screen_color(1, 2), 0x1F);
+        assert_eq!(screen_line(2), " A");
+
+        let start = Instant::now();
+        advance(Duration::from_millis(5));
+        assert!(start.elapsed() >= Duration::from_millis(5));
+    }
+}
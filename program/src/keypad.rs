@@ -0,0 +1,290 @@
+use core::time::Duration;
+
+use crate::board::{try_read_keyboard, Keyboard};
+use crate::ring::RingBuffer;
+use crate::time::{cycles_to_duration, duration_to_cycles, Instant, Ticks};
+
+const EVENT_QUEUE_SIZE: usize = 32;
+
+// The keypad controller only reports that a key went down, and reports
+// nothing while it is held or when it is released. So the release is
+// inferred: a key counts as held until another key is pressed, or until
+// `hold_time` has passed since it went down. A short tap looks the same as
+// a key held that long, so with auto-repeat on it repeats until then too.
+// The timers count `Ticks`, so `update` has to run at least once per wrap
+// of the cycle counter while a key is held.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEventKind {
+    Down,
+    Repeat,
+    Up,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub key: Keyboard,
+    pub kind: KeyEventKind,
+    pub at: Instant,
+}
+
+// Since a held key is released after `hold_time`, it only repeats until
+// then: the `delay` has to be shorter than the `hold_time`, which
+// `Keypad` checks.
+#[derive(Debug, Clone, Copy)]
+pub struct AutoRepeat {
+    pub delay: Duration,
+    pub interval: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct KeypadConfig {
+    pub hold_time: Duration,
+    // `None` disables auto-repeat
+    pub repeat: Option<AutoRepeat>,
+}
+
+impl Default for KeypadConfig {
+    fn default() -> Self {
+        KeypadConfig {
+            hold_time: Duration::from_millis(300),
+            repeat: None,
+        }
+    }
+}
+
+fn check(config: &KeypadConfig) {
+    if let Some(repeat) = config.repeat {
+        assert!(
+            repeat.delay < config.hold_time,
+            "keypad: the repeat delay has to be shorter than the hold time"
+        );
+        assert!(
+            repeat.interval > Duration::ZERO,
+            "keypad: the repeat interval cannot be zero"
+        );
+    }
+}
+
+#[derive(Clone, Copy)]
+struct HeldKey {
+    key: Keyboard,
+    since: Ticks,
+    // when it went down, for the events
+    at: Instant,
+    // cycles after `since`
+    next_repeat: Option<u32>,
+}
+
+pub struct Keypad {
+    config: KeypadConfig,
+    held: Option<HeldKey>,
+    queue: RingBuffer<KeyEvent, EVENT_QUEUE_SIZE>,
+    invalid_codes: u32,
+}
+
+impl Keypad {
+    pub fn new() -> Keypad {
+        Keypad::with_config(KeypadConfig::default())
+    }
+
+    pub fn with_config(config: KeypadConfig) -> Keypad {
+        check(&config);
+        Keypad {
+            config,
+            held: None,
+            queue: RingBuffer::new(),
+            invalid_codes: 0,
+        }
+    }
+
+    pub fn config(&self) -> &KeypadConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: KeypadConfig) {
+        check(&config);
+        self.config = config;
+    }
+
+    fn push(&mut self, key: Keyboard, kind: KeyEventKind, at: Instant) {
+        // the oldest events are dropped if nobody consumes them
+        self.queue.push_overwrite(KeyEvent { key, kind, at });
+    }
+
+    fn release(&mut self, at: Instant) {
+        if let Some(held) = self.held.take() {
+            self.push(held.key, KeyEventKind::Up, at);
+        }
+    }
+
+    // drains the hardware queue, and queues the resulting events
+    pub fn update(&mut self) {
+        let ticks = Ticks::now();
+        let now = Instant::now();
+        self.update_timers(ticks);
+
+        while let Some(code) = try_read_keyboard() {
+            match code {
+                Ok(key) => {
+                    self.release(now);
+                    self.push(key, KeyEventKind::Down, now);
+                    self.held = Some(HeldKey {
+                        key,
+                        since: ticks,
+                        at: now,
+                        next_repeat: self
+                            .config
+                            .repeat
+                            .map(|repeat| duration_to_cycles(repeat.delay)),
+                    });
+                }
+                Err(_) => self.invalid_codes += 1,
+            }
+        }
+    }
+
+    fn update_timers(&mut self, now: Ticks) {
+        let Some(mut held) = self.held else {
+            return;
+        };
+        let elapsed = now.cycles_since(held.since);
+        let hold_time = duration_to_cycles(self.config.hold_time);
+
+        while let Some(cycles) = held.next_repeat {
+            if cycles > elapsed || cycles >= hold_time {
+                break;
+            }
+            let at = held.at + cycles_to_duration(cycles);
+            self.push(held.key, KeyEventKind::Repeat, at);
+            held.next_repeat = self
+                .config
+                .repeat
+                .map(|repeat| cycles.saturating_add(duration_to_cycles(repeat.interval)));
+        }
+        self.held = Some(held);
+
+        if elapsed >= hold_time {
+            self.release(held.at + self.config.hold_time);
+        }
+    }
+
+    pub fn poll(&mut self) -> Option<KeyEvent> {
+        self.update();
+        self.queue.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    pub fn held(&self) -> Option<Keyboard> {
+        self.held.map(|held| held.key)
+    }
+
+    pub fn is_held(&self, key: Keyboard) -> bool {
+        self.held() == Some(key)
+    }
+
+    // number of codes from the hardware that could not be decoded
+    pub fn invalid_codes(&self) -> u32 {
+        self.invalid_codes
+    }
+
+    // blocks until a key goes down (auto-repeats included), dropping any pending events
+    pub fn wait_key(&mut self) -> KeyEvent {
+        self.clear();
+        loop {
+            match self.poll() {
+                Some(event) if event.kind != KeyEventKind::Up => return event,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Default for Keypad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host;
+
+    fn kinds(keypad: &mut Keypad) -> std::vec::Vec<KeyEventKind> {
+        core::iter::from_fn(|| keypad.poll())
+            .map(|event| event.kind)
+            .collect()
+    }
+
+    #[test]
+    fn inferred_release() {
+        host::reset();
+        let mut keypad = Keypad::new();
+        host::push_key(Keyboard::_5);
+        assert_eq!(kinds(&mut keypad), [KeyEventKind::Down]);
+        assert!(keypad.is_held(Keyboard::_5));
+        host::advance(Duration::from_millis(310));
+        assert_eq!(kinds(&mut keypad), [KeyEventKind::Up]);
+        assert_eq!(keypad.held(), None);
+    }
+
+    #[test]
+    fn repeats_until_the_release() {
+        host::reset();
+        let mut keypad = Keypad::with_config(KeypadConfig {
+            hold_time: Duration::from_millis(300),
+            repeat: Some(AutoRepeat {
+                delay: Duration::from_millis(100),
+                interval: Duration::from_millis(50),
+            }),
+        });
+        host::push_key(Keyboard::A);
+        assert_eq!(kinds(&mut keypad), [KeyEventKind::Down]);
+        host::advance(Duration::from_millis(400));
+        // at 100, 150, 200 and 250 ms
+        let mut expected = std::vec![KeyEventKind::Repeat; 4];
+        expected.push(KeyEventKind::Up);
+        assert_eq!(kinds(&mut keypad), expected);
+    }
+
+    #[test]
+    fn held_across_a_wrap() {
+        host::reset();
+        host::with_board(|board| board.cycles = (1 << 32) - 1000);
+        let mut keypad = Keypad::new();
+        host::push_key(Keyboard::B);
+        assert_eq!(kinds(&mut keypad), [KeyEventKind::Down]);
+        host::advance(Duration::from_millis(100));
+        assert!(kinds(&mut keypad).is_empty());
+        host::advance(Duration::from_millis(210));
+        assert_eq!(kinds(&mut keypad), [KeyEventKind::Up]);
+    }
+
+    #[test]
+    #[should_panic(expected = "repeat interval")]
+    fn zero_repeat_interval() {
+        Keypad::with_config(KeypadConfig {
+            hold_time: Duration::from_millis(300),
+            repeat: Some(AutoRepeat {
+                delay: Duration::from_millis(100),
+                interval: Duration::ZERO,
+            }),
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "repeat delay")]
+    fn repeat_delay_past_the_hold_time() {
+        Keypad::with_config(KeypadConfig {
+            hold_time: Duration::from_millis(300),
+            repeat: Some(AutoRepeat {
+                delay: Duration::from_millis(300),
+                interval: Duration::from_millis(50),
+            }),
+        });
+    }
+}
@@ -0,0 +1,212 @@
+// Turns the bytes a terminal sends into key presses: UTF-8 characters,
+// control characters and ANSI escape sequences (arrows, Home, End, ...).
+
+use crate::utf8::{Utf8Decoder, Utf8Error};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Tab,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    // a control character, e.g. `Ctrl('u')` for 0x15
+    Ctrl(char),
+    // an escape sequence we do not know, or a broken UTF-8 character
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Ground,
+    Escape,
+    // `ESC [`, with the first numeric parameter
+    Csi(u16),
+    // `ESC O`
+    Ss3,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct KeyDecoder {
+    state: State,
+    utf8: Utf8Decoder,
+    // the last byte was a CR, so a LF right after it is the same Enter
+    after_cr: bool,
+}
+
+impl KeyDecoder {
+    pub const fn new() -> KeyDecoder {
+        KeyDecoder {
+            state: State::Ground,
+            utf8: Utf8Decoder::new(),
+            after_cr: false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.state = State::Ground;
+        self.utf8.reset();
+        self.after_cr = false;
+    }
+
+    // returns a key once its last byte arrives
+    pub fn push(&mut self, byte: u8) -> Option<Key> {
+        let after_cr = core::mem::take(&mut self.after_cr);
+        match self.state {
+            State::Ground => self.ground(byte, after_cr),
+            State::Escape => {
+                self.state = match byte {
+                    b'[' => State::Csi(0),
+                    b'O' => State::Ss3,
+                    // not a sequence, drop the ESC
+                    _ => return self.ground(byte, false),
+                };
+                None
+            }
+            State::Csi(param) => match byte {
+                b'0'..=b'9' => {
+                    let digit = (byte - b'0') as u16;
+                    self.state = State::Csi(param.saturating_mul(10).saturating_add(digit));
+                    None
+                }
+                // only the first parameter matters, and intermediate bytes are ignored
+                b';' | 0x20..=0x2F => {
+                    self.state = State::Csi(param);
+                    None
+                }
+                0x40..=0x7E => {
+                    self.state = State::Ground;
+                    Some(csi_key(param, byte))
+                }
+                _ => {
+                    self.state = State::Ground;
+                    Some(Key::Unknown)
+                }
+            },
+            State::Ss3 => {
+                self.state = State::Ground;
+                Some(csi_key(0, byte))
+            }
+        }
+    }
+
+    // lines may end with CR, LF or CRLF
+    fn ground(&mut self, byte: u8, after_cr: bool) -> Option<Key> {
+        if self.utf8.is_pending() || byte >= 0x80 {
+            match self.utf8.push(byte) {
+                None => return None,
+                Some(Ok(c)) => return Some(Key::Char(c)),
+                Some(Err(Utf8Error::Invalid)) => return Some(Key::Unknown),
+                // the broken character is dropped, start over with this byte
+                Some(Err(Utf8Error::Interrupted)) => return self.ground(byte, false),
+            }
+        }
+
+        let key = match byte {
+            b'\r' => {
+                self.after_cr = true;
+                Key::Enter
+            }
+            b'\n' if after_cr => return None,
+            b'\n' => Key::Enter,
+            b'\x7f' | b'\x08' => Key::Backspace,
+            b'\t' => Key::Tab,
+            b'\x1b' => {
+                self.state = State::Escape;
+                return None;
+            }
+            0x01..=0x1A => Key::Ctrl((b'a' + byte - 1) as char),
+            0x20..=0x7E => Key::Char(byte as char),
+            _ => Key::Unknown,
+        };
+        Some(key)
+    }
+}
+
+impl Default for KeyDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn csi_key(param: u16, last: u8) -> Key {
+    match (last, param) {
+        (b'A', _) => Key::Up,
+        (b'B', _) => Key::Down,
+        (b'C', _) => Key::Right,
+        (b'D', _) => Key::Left,
+        (b'H', _) => Key::Home,
+        (b'F', _) => Key::End,
+        // VT220 style, `ESC [ n ~`
+        (b'~', 1 | 7) => Key::Home,
+        (b'~', 4 | 8) => Key::End,
+        (b'~', 3) => Key::Delete,
+        _ => Key::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(bytes: &[u8]) -> ([Option<Key>; 8], usize) {
+        let mut decoder = KeyDecoder::new();
+        let mut keys = [None; 8];
+        let mut len = 0;
+        for key in bytes.iter().filter_map(|b| decoder.push(*b)) {
+            keys[len] = Some(key);
+            len += 1;
+        }
+        (keys, len)
+    }
+
+    #[test]
+    fn line_endings() {
+        for ending in [&b"a\r"[..], b"a\n", b"a\r\n"] {
+            let (k, len) = keys(ending);
+            assert_eq!(
+                (k[0], k[1], len),
+                (Some(Key::Char('a')), Some(Key::Enter), 2)
+            );
+        }
+        // empty lines are still there
+        let (k, len) = keys(b"\r\n\r\n\n\r\r");
+        assert_eq!(len, 5);
+        assert!(k[..len].iter().all(|k| *k == Some(Key::Enter)));
+    }
+
+    #[test]
+    fn sequences() {
+        let (k, len) = keys(b"\x1b[A\x1b[3~\x1bOH\x1b[1;5C\x1b[9~\x7f\x01");
+        assert_eq!(
+            k[..len],
+            [
+                Some(Key::Up),
+                Some(Key::Delete),
+                Some(Key::Home),
+                Some(Key::Right),
+                Some(Key::Unknown),
+                Some(Key::Backspace),
+                Some(Key::Ctrl('a')),
+            ]
+        );
+    }
+
+    #[test]
+    fn utf8() {
+        let (k, len) = keys("é\u{1F600}".as_bytes());
+        assert_eq!(
+            k[..len],
+            [Some(Key::Char('é')), Some(Key::Char('\u{1F600}'))]
+        );
+        // a lead byte cut short by a plain character is dropped
+        let (k, len) = keys(b"\xC3x\xFF");
+        assert_eq!(k[..len], [Some(Key::Char('x')), Some(Key::Unknown)]);
+    }
+}
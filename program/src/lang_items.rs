@@ -1,20 +1,25 @@
 use core::panic::PanicInfo;
 
-use crate::println;
+use crate::eprintln;
+use crate::scrollback;
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
     let err = info.message().unwrap();
     if let Some(location) = info.location() {
-        println!(
+        eprintln!(
             "Panicked at {}:{}, {}",
             location.file(),
             location.line(),
             err
         );
     } else {
-        println!("Panicked: {}", err);
+        eprintln!("Panicked: {}", err);
     }
 
+    // the message may have scrolled away
+    if scrollback::is_installed() {
+        scrollback::pager()
+    }
     loop {}
 }
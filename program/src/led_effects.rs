@@ -0,0 +1,272 @@
+use core::ops::Range;
+use core::time::Duration;
+
+use crate::board::{write_leds_masked, LED_COUNT};
+use crate::time::Ticks;
+
+const MAX_ANIMATIONS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    Off,
+    On,
+    Blink { on: Duration, off: Duration },
+    // software PWM, 0 is off and 255 is fully on
+    Brightness(u8),
+    // fades in and out once per `period`
+    Breathe { period: Duration },
+}
+
+// drives a range of LEDs, overriding the per-LED effects there
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Animation<'a> {
+    // Knight-Rider style: one lit LED bouncing between both ends
+    Chase {
+        start: usize,
+        len: usize,
+        step: Duration,
+    },
+    // shows `patterns` one after another, bit 0 of each pattern is LED `start`
+    Sequence {
+        start: usize,
+        len: usize,
+        patterns: &'a [u32],
+        step: Duration,
+        repeat: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationId(usize);
+
+// time since an effect started, added up from `Ticks` at each poll, so it
+// keeps counting across wraps of the cycle counter
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    last: Ticks,
+    elapsed: Duration,
+}
+
+impl Clock {
+    fn start(now: Ticks) -> Clock {
+        Clock {
+            last: now,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    fn advance(&mut self, now: Ticks) -> Duration {
+        self.elapsed += now.duration_since(self.last);
+        self.last = now;
+        self.elapsed
+    }
+}
+
+pub struct LedEffects<'a> {
+    effects: [Option<(Effect, Clock)>; LED_COUNT],
+    animations: [Option<(Animation<'a>, Clock)>; MAX_ANIMATIONS],
+    pwm_period: Duration,
+}
+
+#[inline]
+fn micros(duration: Duration) -> u64 {
+    duration.as_micros() as u64
+}
+
+fn pwm(level: u8, elapsed: Duration, period: Duration) -> bool {
+    let period = micros(period).max(1);
+    let phase = micros(elapsed) % period;
+    phase * 255 < level as u64 * period
+}
+
+impl Effect {
+    fn is_on(&self, elapsed: Duration, pwm_period: Duration) -> bool {
+        match *self {
+            Effect::Off => false,
+            Effect::On => true,
+            Effect::Blink { on, off } => {
+                let period = micros(on + off).max(1);
+                micros(elapsed) % period < micros(on)
+            }
+            Effect::Brightness(level) => pwm(level, elapsed, pwm_period),
+            Effect::Breathe { period } => {
+                let period = micros(period).max(2);
+                let half = period / 2;
+                let phase = micros(elapsed) % period;
+                let distance = if phase < half { phase } else { period - phase };
+                let linear = distance * 255 / half;
+                // squared, so the fading looks even to the eye
+                let level = (linear.min(255) * linear.min(255) / 255) as u8;
+                pwm(level, elapsed, pwm_period)
+            }
+        }
+    }
+}
+
+impl Animation<'_> {
+    fn range(&self) -> Range<usize> {
+        match *self {
+            Animation::Chase { start, len, .. } | Animation::Sequence { start, len, .. } => {
+                start..start + len
+            }
+        }
+    }
+
+    // the state of the animated range, bit 0 is the first LED of the range
+    fn frame(&self, elapsed: Duration) -> u32 {
+        match *self {
+            Animation::Chase { len, step, .. } => {
+                if len <= 1 {
+                    return len as u32;
+                }
+                let bounce = 2 * (len as u64 - 1);
+                let k = (micros(elapsed) / micros(step).max(1)) % bounce;
+                let position = if k < len as u64 { k } else { bounce - k };
+                1 << position
+            }
+            Animation::Sequence {
+                patterns,
+                step,
+                repeat,
+                ..
+            } => {
+                if patterns.is_empty() {
+                    return 0;
+                }
+                let k = (micros(elapsed) / micros(step).max(1)) as usize;
+                let k = if repeat {
+                    k % patterns.len()
+                } else {
+                    k.min(patterns.len() - 1)
+                };
+                patterns[k]
+            }
+        }
+    }
+}
+
+impl<'a> LedEffects<'a> {
+    pub fn new() -> LedEffects<'a> {
+        LedEffects {
+            effects: [None; LED_COUNT],
+            animations: [None; MAX_ANIMATIONS],
+            pwm_period: Duration::from_millis(10),
+        }
+    }
+
+    pub fn set_pwm_period(&mut self, period: Duration) {
+        self.pwm_period = period;
+    }
+
+    // restarts the effect of LED `index` from its beginning
+    pub fn set(&mut self, index: usize, effect: Effect) {
+        assert!(index < LED_COUNT);
+        self.effects[index] = Some((effect, Clock::start(Ticks::now())));
+    }
+
+    pub fn set_range(&mut self, range: Range<usize>, effect: Effect) {
+        let now = Ticks::now();
+        for index in range {
+            assert!(index < LED_COUNT);
+            self.effects[index] = Some((effect, Clock::start(now)));
+        }
+    }
+
+    // sets LEDs in `range` by the bits of `mask`, bit 0 is the first LED of the range
+    pub fn set_mask(&mut self, range: Range<usize>, mask: u32, on: Effect, off: Effect) {
+        let start = range.start;
+        for index in range {
+            self.set(
+                index,
+                if mask & (1 << (index - start)) != 0 {
+                    on
+                } else {
+                    off
+                },
+            );
+        }
+    }
+
+    // leaves LED `index` alone in later polls
+    pub fn release(&mut self, index: usize) {
+        self.effects[index] = None;
+    }
+
+    pub fn start(&mut self, animation: Animation<'a>) -> Option<AnimationId> {
+        assert!(animation.range().end <= LED_COUNT);
+        let index = self.animations.iter().position(|slot| slot.is_none())?;
+        self.animations[index] = Some((animation, Clock::start(Ticks::now())));
+        Some(AnimationId(index))
+    }
+
+    pub fn stop(&mut self, id: AnimationId) {
+        self.animations[id.0] = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.effects = [None; LED_COUNT];
+        self.animations = [None; MAX_ANIMATIONS];
+        write_leds_masked(0, (1 << LED_COUNT) - 1);
+    }
+
+    // call it as often as possible, PWM effects need at least several polls per PWM period,
+    // and the timing goes wrong if the cycle counter wraps between two polls
+    pub fn poll(&mut self) {
+        let now = Ticks::now();
+        let mut value = 0;
+        let mut select = 0;
+
+        for (index, effect) in self.effects.iter_mut().enumerate() {
+            if let Some((effect, clock)) = effect {
+                let elapsed = clock.advance(now);
+                value |= (effect.is_on(elapsed, self.pwm_period) as u32) << index;
+                select |= 1 << index;
+            }
+        }
+
+        for (animation, clock) in self.animations.iter_mut().flatten() {
+            let range = animation.range();
+            let mask = ((1u64 << range.len()) - 1) as u32;
+            let frame = animation.frame(clock.advance(now)) & mask;
+            value = value & !(mask << range.start) | frame << range.start;
+            select |= mask << range.start;
+        }
+
+        write_leds_masked(value, select);
+    }
+}
+
+impl Default for LedEffects<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host;
+
+    #[test]
+    fn sequence_across_a_wrap() {
+        host::reset();
+        host::with_board(|board| board.cycles = (1 << 32) - 5000);
+        let mut effects = LedEffects::new();
+        effects.start(Animation::Sequence {
+            start: 0,
+            len: 2,
+            patterns: &[0b01, 0b10],
+            step: Duration::from_millis(1),
+            repeat: false,
+        });
+        effects.poll();
+        assert_eq!(host::leds(), 0b01);
+        // past the wrap, the sequence stays at its end
+        host::advance(Duration::from_millis(1));
+        effects.poll();
+        assert_eq!(host::leds(), 0b10);
+        host::advance(Duration::from_millis(1));
+        effects.poll();
+        assert_eq!(host::leds(), 0b10);
+    }
+}
@@ -1,23 +1,49 @@
 #![no_std]
 #![feature(let_chains)]
-#![feature(linkage)]
+#![cfg_attr(target_arch = "riscv32", feature(linkage))]
 #![feature(panic_info_message)]
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(not(target_arch = "riscv32"))]
+extern crate std;
 
+#[cfg(target_arch = "riscv32")]
 use core::arch::global_asm;
 
+pub mod ansi;
 pub mod board;
+pub mod button;
+pub mod codepage;
 pub mod console;
+pub mod framebuffer;
+pub mod hal;
+#[cfg(not(target_arch = "riscv32"))]
+pub mod host;
+pub mod keypad;
+pub mod keys;
+#[cfg(target_arch = "riscv32")]
 mod lang_items;
+pub mod led_effects;
+pub mod line_editor;
+pub mod link;
+pub mod logger;
+pub mod mmio;
 pub mod monitor;
+pub mod platform;
+pub mod reset;
+pub mod ring;
 pub mod rng;
+pub mod scan;
+pub mod scrollback;
 pub mod time;
 pub mod tube;
+pub mod tui;
 pub mod uart;
+pub mod utf8;
+pub mod window;
 
-#[cfg(feature = "alloc")]
+#[cfg(all(feature = "alloc", target_arch = "riscv32"))]
 mod allocator;
 
 pub mod prelude;
@@ -25,24 +51,28 @@ pub mod prelude;
 // #[cfg(feature = "loader")]
 // global_asm!(include_str!("entry-loader.asm"));
 // #[cfg(not(feature = "loader"))]
+#[cfg(target_arch = "riscv32")]
 global_asm!(include_str!("entry.asm"));
 
+#[cfg(target_arch = "riscv32")]
 #[linkage = "weak"]
 #[no_mangle]
 fn main() -> i32 {
     panic!("Cannot find main!");
 }
 
+#[cfg(target_arch = "riscv32")]
 #[no_mangle]
 #[link_section = ".text.entry"]
 pub extern "C" fn rust_main() -> i32 {
     clear_bss();
+    reset::init();
     monitor::init();
     monitor::monitor::clear_screen();
     main()
 }
 
-#[allow(dead_code)]
+#[cfg(target_arch = "riscv32")]
 fn clear_bss() {
     extern "C" {
         fn sbss();
@@ -0,0 +1,362 @@
+// Line editing for `console::read_line`: cursor movement, insertion and
+// deletion anywhere in the line, and a history of entered lines.
+//
+// Keys:
+//   Left, Right, Ctrl-B, Ctrl-F   move by a character
+//   Home, End, Ctrl-A, Ctrl-E     move to the start or the end
+//   Backspace, Delete, Ctrl-D     delete a character
+//   Ctrl-U, Ctrl-K                delete to the start or the end
+//   Ctrl-W                        delete the word before the cursor
+//   Up, Down, Ctrl-P, Ctrl-N      go through the history
+//   Ctrl-C                        give up the line
+//
+// The line is echoed with ANSI sequences to where stdout goes, except the
+// screen, where it is drawn at the monitor's cursor. Only whole, valid UTF-8 characters get
+// into the buffer.
+
+use core::cmp::Ordering;
+use core::fmt::{self, Write};
+
+use crate::codepage;
+use crate::console;
+use crate::keys::{Key, KeyDecoder};
+use crate::monitor::{self, monitor as cursor, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::ring::RingBuffer;
+use crate::scrollback;
+
+pub const HISTORY_SIZE: usize = 8;
+// longer lines are not remembered
+pub const HISTORY_LINE_MAX: usize = 126;
+
+#[derive(Clone, Copy)]
+struct HistoryLine {
+    buf: [u8; HISTORY_LINE_MAX],
+    len: u8,
+}
+
+pub struct History {
+    lines: RingBuffer<HistoryLine, HISTORY_SIZE>,
+}
+
+impl History {
+    pub const fn new() -> History {
+        History {
+            lines: RingBuffer::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    // blank lines and repeats of the last line are skipped
+    pub fn push(&mut self, line: &str) {
+        if line.trim().is_empty() || line.len() > HISTORY_LINE_MAX || self.get(0) == Some(line) {
+            return;
+        }
+        let mut entry = HistoryLine {
+            buf: [0; HISTORY_LINE_MAX],
+            len: line.len() as u8,
+        };
+        entry.buf[..line.len()].copy_from_slice(line.as_bytes());
+        self.lines.push_overwrite(entry);
+    }
+
+    // 0 is the newest
+    pub fn get(&self, index: usize) -> Option<&str> {
+        let len = self.lines.len();
+        if index >= len {
+            return None;
+        }
+        let entry = self.lines.get_ref(len - 1 - index)?;
+        core::str::from_utf8(&entry.buf[..entry.len as usize]).ok()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn char_count(s: &[u8]) -> usize {
+    // the buffer only holds valid UTF-8, so count the non-continuation bytes
+    s.iter().filter(|b| **b & 0xC0 != 0x80).count()
+}
+
+pub struct LineEditor<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+    // byte offset, always on a character boundary
+    cursor: usize,
+    history: Option<&'a mut History>,
+    // the history entry shown, if any
+    browsing: Option<usize>,
+    decoder: KeyDecoder,
+    screen: bool,
+    // where the line starts on the screen
+    screen_x: usize,
+    screen_y: usize,
+    // characters drawn by the last redraw, to erase leftovers
+    drawn: usize,
+}
+
+impl<'a> LineEditor<'a> {
+    // `screen` also draws the line on the VGA screen
+    pub fn new(buf: &'a mut [u8], history: Option<&'a mut History>, screen: bool) -> Self {
+        LineEditor {
+            buf,
+            len: 0,
+            cursor: 0,
+            history,
+            browsing: None,
+            decoder: KeyDecoder::new(),
+            screen,
+            screen_x: cursor::get_x(),
+            screen_y: cursor::get_y(),
+            drawn: 0,
+        }
+    }
+
+    pub fn line(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+
+    // feeds one byte of input, returns `true` once the line is done
+    pub fn push(&mut self, byte: u8) -> bool {
+        match self.decoder.push(byte) {
+            Some(key) => self.push_key(key),
+            None => false,
+        }
+    }
+
+    // feeds a key from a decoder of your own, which can outlive the line
+    pub fn push_key(&mut self, key: Key) -> bool {
+        self.handle(key)
+    }
+
+    // moves to the next line, and remembers the line in the history
+    pub fn finish(self) -> &'a str {
+        let LineEditor {
+            buf, len, history, ..
+        } = self;
+        let line = core::str::from_utf8(&buf[..len]).unwrap_or("");
+        if let Some(history) = history {
+            history.push(line);
+        }
+        line
+    }
+
+    fn handle(&mut self, key: Key) -> bool {
+        match key {
+            Key::Enter => {
+                self.move_to(self.len);
+                self.newline();
+                return true;
+            }
+            Key::Ctrl('c') => {
+                self.move_to(self.len);
+                echo(b"^C");
+                self.len = 0;
+                self.cursor = 0;
+                self.newline();
+                return true;
+            }
+            Key::Char(c) => self.insert(c),
+            Key::Tab => self.insert(' '),
+            Key::Left | Key::Ctrl('b') => self.move_to(self.prev_boundary(self.cursor)),
+            Key::Right | Key::Ctrl('f') => self.move_to(self.next_boundary(self.cursor)),
+            Key::Home | Key::Ctrl('a') => self.move_to(0),
+            Key::End | Key::Ctrl('e') => self.move_to(self.len),
+            Key::Backspace => {
+                let start = self.prev_boundary(self.cursor);
+                self.delete(start, self.cursor);
+            }
+            Key::Delete | Key::Ctrl('d') => {
+                let end = self.next_boundary(self.cursor);
+                self.delete(self.cursor, end);
+            }
+            Key::Ctrl('u') => self.delete(0, self.cursor),
+            Key::Ctrl('k') => self.delete(self.cursor, self.len),
+            Key::Ctrl('w') => {
+                let before = &self.buf[..self.cursor];
+                let end = before.iter().rposition(|b| *b != b' ').map_or(0, |i| i + 1);
+                let start = before[..end]
+                    .iter()
+                    .rposition(|b| *b == b' ')
+                    .map_or(0, |i| i + 1);
+                self.delete(start, self.cursor);
+            }
+            Key::Up | Key::Ctrl('p') => {
+                let next = self.browsing.map_or(0, |i| i + 1);
+                self.recall(Some(next));
+            }
+            Key::Down | Key::Ctrl('n') => {
+                let next = self.browsing.and_then(|i| i.checked_sub(1));
+                self.recall(next);
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn prev_boundary(&self, pos: usize) -> usize {
+        (0..pos)
+            .rev()
+            .find(|i| self.buf[*i] & 0xC0 != 0x80)
+            .unwrap_or(0)
+    }
+
+    fn next_boundary(&self, pos: usize) -> usize {
+        (pos + 1..self.len)
+            .find(|i| self.buf[*i] & 0xC0 != 0x80)
+            .unwrap_or(self.len)
+    }
+
+    fn insert(&mut self, c: char) {
+        let mut encoded = [0; 4];
+        let encoded = c.encode_utf8(&mut encoded).as_bytes();
+        if self.len + encoded.len() > self.buf.len() {
+            return;
+        }
+        self.buf
+            .copy_within(self.cursor..self.len, self.cursor + encoded.len());
+        self.buf[self.cursor..self.cursor + encoded.len()].copy_from_slice(encoded);
+        self.len += encoded.len();
+        let from = self.cursor;
+        self.cursor += encoded.len();
+        self.redraw(from);
+    }
+
+    fn delete(&mut self, start: usize, end: usize) {
+        if start >= end {
+            return;
+        }
+        self.move_to(start);
+        self.buf.copy_within(end..self.len, start);
+        self.len -= end - start;
+        self.redraw(start);
+    }
+
+    // replaces the line with a history entry, or an empty line for `None`
+    fn recall(&mut self, index: Option<usize>) {
+        let len = match (&self.history, index) {
+            (Some(history), Some(index)) => match history.get(index) {
+                Some(line) if line.len() <= self.buf.len() => line.len(),
+                // past the oldest, or too long
+                _ => return,
+            },
+            (Some(_), None) => 0,
+            (None, _) => return,
+        };
+
+        self.move_to(0);
+        if let (Some(history), Some(index)) = (&self.history, index) {
+            self.buf[..len].copy_from_slice(history.get(index).unwrap().as_bytes());
+        }
+        self.browsing = index;
+        self.len = len;
+        self.cursor = len;
+        self.redraw(0);
+    }
+
+    fn move_to(&mut self, pos: usize) {
+        let (from, to) = (
+            char_count(&self.buf[..self.cursor]),
+            char_count(&self.buf[..pos]),
+        );
+        self.cursor = pos;
+        echo_move(from, to);
+        if self.screen {
+            self.screen_move(to);
+        }
+    }
+
+    // draws the line from byte offset `from`, where the terminal's cursor
+    // is, to the end, and puts the cursor at `self.cursor`
+    fn redraw(&mut self, from: usize) {
+        let count = char_count(&self.buf[..self.len]);
+        let cursor = char_count(&self.buf[..self.cursor]);
+
+        echo(&self.buf[from..self.len]);
+        if count < self.drawn {
+            echo(b"\x1b[K");
+        }
+        echo_move(count, cursor);
+
+        if self.screen {
+            self.screen_redraw(count);
+            self.screen_move(cursor);
+            monitor::flush();
+        }
+        self.drawn = count;
+    }
+
+    fn screen_position(&self, column: usize) -> (usize, usize) {
+        let offset = self.screen_x + column;
+        (offset % SCREEN_WIDTH, self.screen_y + offset / SCREEN_WIDTH)
+    }
+
+    fn screen_redraw(&mut self, count: usize) {
+        scrollback::leave();
+        // scroll first if the line runs past the bottom
+        let (_, last_row) = self.screen_position(count.max(self.drawn));
+        for _ in SCREEN_HEIGHT..=last_row {
+            cursor::scroll_down();
+            self.screen_y = self.screen_y.saturating_sub(1);
+        }
+
+        let color = cursor::get_color();
+        let line = self.line();
+        let chars = line.chars().map(codepage::from_char);
+        let blanks = core::iter::repeat_n(b' ', self.drawn.saturating_sub(count));
+        for (i, ch) in chars.chain(blanks).enumerate() {
+            let (x, y) = self.screen_position(i);
+            monitor::set_character(x, y, ch);
+            monitor::set_color(x, y, color);
+        }
+    }
+
+    fn screen_move(&self, column: usize) {
+        let (x, y) = self.screen_position(column);
+        cursor::set_xy(x, y.min(SCREEN_HEIGHT - 1));
+    }
+
+    fn newline(&mut self) {
+        echo(b"\r\n");
+        if self.screen {
+            self.screen_move(char_count(&self.buf[..self.len]));
+            cursor::newline();
+        }
+    }
+}
+
+fn echo(data: &[u8]) {
+    console::echo_bytes(data);
+}
+
+struct Echo;
+
+impl Write for Echo {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        echo(s.as_bytes());
+        Ok(())
+    }
+}
+
+// moves the terminal cursor from one column of the line to another
+fn echo_move(from: usize, to: usize) {
+    let _ = match to.cmp(&from) {
+        Ordering::Greater => write!(Echo, "\x1b[{}C", to - from),
+        Ordering::Less => write!(Echo, "\x1b[{}D", from - to),
+        Ordering::Equal => Ok(()),
+    };
+}
@@ -0,0 +1,36 @@
+// Reliable messages over the UART, see the `cpu_link` crate for the protocol.
+// The host side is in `host/` at the top of the repository.
+
+use core::convert::Infallible;
+use core::time::Duration;
+
+pub use crate::uart::Uart;
+pub use cpu_link::{Config, Stats, Transport, MAX_PAYLOAD};
+
+use crate::time::Instant;
+use crate::uart;
+
+pub type Error = cpu_link::Error<Infallible>;
+
+impl Transport for Uart {
+    type Error = Infallible;
+
+    fn read(&mut self) -> Result<Option<u8>, Infallible> {
+        Ok(uart::try_read())
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), Infallible> {
+        data.iter().for_each(|b| uart::write(*b));
+        Ok(())
+    }
+
+    fn now(&mut self) -> Duration {
+        Duration::from_micros(Instant::now().as_micros())
+    }
+}
+
+pub type Link = cpu_link::Endpoint<Uart>;
+
+pub fn open() -> Link {
+    Link::new(Uart)
+}
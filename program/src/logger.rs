@@ -0,0 +1,242 @@
+// A backend for the `log` crate. Records go to a console stream, with the
+// time since boot, the level and the target (the module path by default):
+//
+//     12.034517 DEBUG tetris::input: Input: Left
+//
+// The `log_level_*` features cap the level at compile time, what is left
+// can be filtered at run time, per module and from the switches.
+
+use core::fmt;
+use core::ops::Range;
+use core::ptr::addr_of;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::board::switches;
+use crate::console::{self, Stream};
+use crate::time::Instant;
+use crate::uart;
+
+pub struct Config {
+    pub level: LevelFilter,
+    // if set, the level is read from these switches for every record,
+    // see `level_from_switches`
+    pub switches: Option<Range<usize>>,
+    // per module levels, the longest matching prefix of the target wins
+    pub modules: &'static [(&'static str, LevelFilter)],
+    pub stream: Stream,
+    // color the level with ANSI sequences
+    pub color: bool,
+}
+
+impl Config {
+    pub const DEFAULT: Config = Config {
+        level: LevelFilter::Info,
+        switches: None,
+        modules: &[],
+        stream: Stream::Stderr,
+        color: true,
+    };
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config::DEFAULT
+    }
+}
+
+static mut CONFIG: Config = Config::DEFAULT;
+
+struct Logger;
+
+static LOGGER: Logger = Logger;
+
+// the switches as a number, 0 is off, 1 is error, ..., 5 and above is trace
+pub fn level_from_switches(range: Range<usize>) -> LevelFilter {
+    LevelFilter::iter()
+        .nth(switches(range) as usize)
+        .unwrap_or(LevelFilter::Trace)
+}
+
+fn config() -> &'static Config {
+    unsafe { &*addr_of!(CONFIG) }
+}
+
+// lets the `log` macros skip what can never be logged
+fn update_max_level() {
+    let config = config();
+    let max = match config.switches {
+        Some(_) => LevelFilter::Trace,
+        None => config
+            .modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(config.level, Ord::max),
+    };
+    // there are no other threads
+    unsafe { log::set_max_level_racy(max) }
+}
+
+pub fn init(config: Config) {
+    unsafe {
+        CONFIG = config;
+        // fails only if already set, to this same logger
+        let _ = log::set_logger_racy(&LOGGER);
+    }
+    update_max_level();
+}
+
+pub fn set_level(level: LevelFilter) {
+    unsafe { CONFIG.level = level }
+    update_max_level();
+}
+
+pub fn set_stream(stream: Stream) {
+    unsafe { CONFIG.stream = stream }
+}
+
+fn level_for(target: &str) -> LevelFilter {
+    let config = config();
+    let module = config
+        .modules
+        .iter()
+        .filter(|(prefix, _)| {
+            target
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        })
+        .max_by_key(|(prefix, _)| prefix.len());
+    match (module, &config.switches) {
+        (Some((_, level)), _) => *level,
+        (None, Some(range)) => level_from_switches(range.clone()),
+        (None, None) => config.level,
+    }
+}
+
+fn level_color(level: Level) -> &'static str {
+    match level {
+        Level::Error => "\x1b[31m",
+        Level::Warn => "\x1b[33m",
+        Level::Info => "\x1b[32m",
+        Level::Debug => "\x1b[36m",
+        Level::Trace => "\x1b[90m",
+    }
+}
+
+fn output(stream: Stream, args: fmt::Arguments) {
+    match stream {
+        Stream::Stdout => console::print(args),
+        Stream::Stderr => console::eprint(args),
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let config = config();
+        let micros = Instant::now().as_micros();
+        let (color, reset) = if config.color {
+            (level_color(record.level()), "\x1b[0m")
+        } else {
+            ("", "")
+        };
+        output(
+            config.stream,
+            format_args!(
+                "{:4}.{:06} {}{:5}{} {}: {}\r\n",
+                micros / 1_000_000,
+                micros % 1_000_000,
+                color,
+                record.level(),
+                reset,
+                record.target(),
+                record.args()
+            ),
+        );
+    }
+
+    fn flush(&self) {
+        uart::flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::{set_sinks, Sinks};
+    use crate::host::{self, take_uart_output};
+
+    #[test]
+    fn module_levels() {
+        host::reset();
+        init(Config {
+            level: LevelFilter::Warn,
+            modules: &[
+                ("game", LevelFilter::Debug),
+                ("game::input", LevelFilter::Trace),
+                ("net", LevelFilter::Off),
+            ],
+            ..Config::DEFAULT
+        });
+        // the longest prefix wins
+        assert_eq!(level_for("game"), LevelFilter::Debug);
+        assert_eq!(level_for("game::draw"), LevelFilter::Debug);
+        assert_eq!(level_for("game::input::keys"), LevelFilter::Trace);
+        // only whole module names match
+        assert_eq!(level_for("game::inputs"), LevelFilter::Debug);
+        assert_eq!(level_for("gamepad"), LevelFilter::Warn);
+        assert_eq!(level_for("net::link"), LevelFilter::Off);
+
+        init(Config {
+            switches: Some(0..3),
+            modules: &[("game", LevelFilter::Error)],
+            ..Config::DEFAULT
+        });
+        host::set_switches(4);
+        assert_eq!(level_for("other"), LevelFilter::Debug);
+        // a module level is kept whatever the switches say
+        assert_eq!(level_for("game::draw"), LevelFilter::Error);
+        init(Config::DEFAULT);
+    }
+
+    fn logged(config: Config) -> std::string::String {
+        init(config);
+        take_uart_output();
+        log::log!(target: "demo", Level::Info, "hello {}", 7);
+        init(Config::DEFAULT);
+        std::string::String::from_utf8(take_uart_output()).unwrap()
+    }
+
+    #[test]
+    fn format() {
+        host::reset();
+        set_sinks(Stream::Stdout, Sinks::UART);
+        let config = || Config {
+            stream: Stream::Stdout,
+            color: false,
+            ..Config::DEFAULT
+        };
+
+        let output = logged(config());
+        let (time, rest) = output.trim_start().split_once(' ').unwrap();
+        let (seconds, micros) = time.split_once('.').unwrap();
+        assert!(seconds.parse::<u64>().is_ok());
+        assert!(micros.len() == 6 && micros.parse::<u32>().is_ok());
+        assert_eq!(rest, "INFO  demo: hello 7\r\n");
+
+        let output = logged(Config {
+            color: true,
+            ..config()
+        });
+        assert!(output.ends_with(" \x1b[32mINFO \x1b[0m demo: hello 7\r\n"));
+
+        set_sinks(Stream::Stdout, Sinks::UART | Sinks::SCREEN);
+    }
+}
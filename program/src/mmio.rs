@@ -0,0 +1,163 @@
+// Memory-mapped I/O register map (see "Memory-mapped I/O 地址" in the top-level README)
+
+use core::marker::PhantomData;
+#[cfg(target_arch = "riscv32")]
+use core::ptr::{read_volatile, write_volatile};
+
+pub struct R;
+pub struct W;
+pub struct RW;
+
+pub trait Readable {}
+pub trait Writable {}
+
+impl Readable for R {}
+impl Readable for RW {}
+impl Writable for W {}
+impl Writable for RW {}
+
+pub struct Register<A> {
+    addr: usize,
+    _access: PhantomData<A>,
+}
+
+pub type ReadOnly = Register<R>;
+pub type WriteOnly = Register<W>;
+pub type ReadWrite = Register<RW>;
+
+impl<A> Register<A> {
+    pub const fn new(addr: usize) -> Register<A> {
+        Register {
+            addr,
+            _access: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub const fn addr(&self) -> usize {
+        self.addr
+    }
+}
+
+// all accesses go through these two, so that the host backend can take over
+#[cfg(target_arch = "riscv32")]
+#[inline]
+pub(crate) unsafe fn read_raw(addr: usize) -> u32 {
+    read_volatile(addr as *const u32)
+}
+
+#[cfg(target_arch = "riscv32")]
+#[inline]
+pub(crate) unsafe fn write_raw(addr: usize, data: u32) {
+    write_volatile(addr as *mut u32, data)
+}
+
+#[cfg(not(target_arch = "riscv32"))]
+#[inline]
+pub(crate) unsafe fn read_raw(addr: usize) -> u32 {
+    crate::host::read(addr)
+}
+
+#[cfg(not(target_arch = "riscv32"))]
+#[inline]
+pub(crate) unsafe fn write_raw(addr: usize, data: u32) {
+    crate::host::write(addr, data)
+}
+
+impl<A: Readable> Register<A> {
+    #[inline]
+    pub fn read(&self) -> u32 {
+        unsafe { read_raw(self.addr) }
+    }
+}
+
+impl<A: Writable> Register<A> {
+    #[inline]
+    pub fn write(&self, data: u32) {
+        unsafe { write_raw(self.addr, data) }
+    }
+}
+
+impl<A: Readable + Writable> Register<A> {
+    #[inline]
+    pub fn modify(&self, f: impl FnOnce(u32) -> u32) {
+        self.write(f(self.read()))
+    }
+}
+
+// consecutive 4-byte aligned registers with the same access
+pub struct RegisterArray<A, const N: usize> {
+    base: usize,
+    _access: PhantomData<A>,
+}
+
+impl<A, const N: usize> RegisterArray<A, N> {
+    pub const fn new(base: usize) -> RegisterArray<A, N> {
+        RegisterArray {
+            base,
+            _access: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> Register<A> {
+        assert!(index < N);
+        Register::new(self.base + index * 4)
+    }
+}
+
+impl<A: Readable, const N: usize> RegisterArray<A, N> {
+    #[inline]
+    pub fn read(&self, index: usize) -> u32 {
+        self.get(index).read()
+    }
+}
+
+impl<A: Writable, const N: usize> RegisterArray<A, N> {
+    #[inline]
+    pub fn write(&self, index: usize, data: u32) {
+        self.get(index).write(data)
+    }
+}
+
+pub const UART_RX: ReadOnly = Register::new(0xFFFFF000);
+pub const UART_TX: WriteOnly = Register::new(0xFFFFF004);
+pub const UART_RX_READY: ReadOnly = Register::new(0xFFFFF008);
+pub const UART_TX_READY: ReadOnly = Register::new(0xFFFFF00C);
+
+pub const CYCLES: ReadOnly = Register::new(0xFFFFF010);
+
+pub const KEYBOARD_DATA: ReadOnly = Register::new(0xFFFFF014);
+pub const KEYBOARD_READY: ReadOnly = Register::new(0xFFFFF018);
+
+// in the order of `board::Button`: center, up, down, left, right
+pub const BUTTONS: RegisterArray<R, 5> = RegisterArray::new(0xFFFFF01C);
+
+pub const TUBE_MODE: WriteOnly = Register::new(0xFFFFF030);
+pub const TUBE_VALUE: WriteOnly = Register::new(0xFFFFF038);
+pub const TUBE_ENABLE: WriteOnly = Register::new(0xFFFFF03C);
+
+pub const INTERRUPT_PC: ReadOnly = Register::new(0xFFFFF040);
+pub const RANDOM: ReadOnly = Register::new(0xFFFFF044);
+
+pub const SWITCHES: RegisterArray<R, 24> = RegisterArray::new(0xFFFFF100);
+pub const LEDS: RegisterArray<W, 24> = RegisterArray::new(0xFFFFF200);
+
+// the VGA buffers are 256x64 cells, only the top-left 100x37 is shown
+pub const VGA_BUFFER_WIDTH: usize = 256;
+pub const VGA_BUFFER_HEIGHT: usize = 64;
+
+pub const VGA_CHARS: RegisterArray<RW, { VGA_BUFFER_WIDTH * VGA_BUFFER_HEIGHT }> =
+    RegisterArray::new(0xFFFD0000);
+pub const VGA_COLORS: RegisterArray<RW, { VGA_BUFFER_WIDTH * VGA_BUFFER_HEIGHT }> =
+    RegisterArray::new(0xFFFE0000);
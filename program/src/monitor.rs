@@ -1,9 +1,16 @@
-const DATA_BASEADDR: *mut u32 = 0xFFFD0000 as *mut u32;
-const COLOR_BASEADDR: *mut u32 = 0xFFFE0000 as *mut u32;
+use core::fmt;
+use core::ptr::addr_of_mut;
 
-pub const SCREEN_BUFFER_MULTIPLIER: usize = 256;
+use crate::ansi::{Action, Attributes, Csi, Parser};
+use crate::codepage;
+use crate::framebuffer::Framebuffer;
+use crate::mmio::{VGA_BUFFER_WIDTH, VGA_CHARS, VGA_COLORS};
+use crate::scrollback;
+
+pub const SCREEN_BUFFER_MULTIPLIER: usize = VGA_BUFFER_WIDTH;
 pub const SCREEN_WIDTH: usize = 800 / 8;
 pub const SCREEN_HEIGHT: usize = 600 / 16;
+pub const TAB_WIDTH: usize = 8;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Color {
@@ -44,25 +51,127 @@ fn offset(x: usize, y: usize) -> usize {
     y * SCREEN_BUFFER_MULTIPLIER + x
 }
 
+// the VGA buffers themselves, bypassing the shadow
+pub mod vga {
+    use super::*;
+
+    pub fn set_character(x: usize, y: usize, ch: u8) {
+        VGA_CHARS.write(offset(x, y), ch as u32)
+    }
+
+    pub fn get_character(x: usize, y: usize) -> u8 {
+        VGA_CHARS.read(offset(x, y)) as u8
+    }
+
+    pub fn set_color(x: usize, y: usize, ch: u8) {
+        VGA_COLORS.write(offset(x, y), ch as u32)
+    }
+
+    pub fn get_color(x: usize, y: usize) -> u8 {
+        VGA_COLORS.read(offset(x, y)) as u8
+    }
+}
+
+static mut SHADOW: Option<&'static mut Framebuffer> = None;
+
+// the shadow only covers the visible part
+fn shadow(x: usize, y: usize) -> Option<&'static mut Framebuffer> {
+    if x < SCREEN_WIDTH && y < SCREEN_HEIGHT {
+        unsafe { (*addr_of_mut!(SHADOW)).as_deref_mut() }
+    } else {
+        None
+    }
+}
+
+// from now on, drawing goes to `framebuffer` until `flush`
+pub fn install_shadow(framebuffer: &'static mut Framebuffer) {
+    framebuffer.load();
+    unsafe { SHADOW = Some(framebuffer) }
+}
+
+// flushes, and draws directly to the screen again
+pub fn remove_shadow() -> Option<&'static mut Framebuffer> {
+    let framebuffer = unsafe { (*addr_of_mut!(SHADOW)).take() }?;
+    framebuffer.flush();
+    Some(framebuffer)
+}
+
+pub fn has_shadow() -> bool {
+    unsafe { (*addr_of_mut!(SHADOW)).is_some() }
+}
+
+// writes what changed in the shadow to the screen
+pub fn flush() {
+    if let Some(framebuffer) = unsafe { (*addr_of_mut!(SHADOW)).as_deref_mut() } {
+        framebuffer.flush();
+    }
+}
+
+// While `scrollback` pages, the screen shows the history, and drawing goes
+// to the live screen it keeps aside.
 pub fn set_character(x: usize, y: usize, ch: u8) {
-    unsafe { DATA_BASEADDR.add(offset(x, y)).write_volatile(ch as u32) }
+    match scrollback::live_character(x, y) {
+        Some(cell) => *cell = ch,
+        None => set_visible_character(x, y, ch),
+    }
 }
 
 pub fn get_character(x: usize, y: usize) -> u8 {
-    unsafe { DATA_BASEADDR.add(offset(x, y)).read_volatile() as u8 }
+    match scrollback::live_character(x, y) {
+        Some(cell) => *cell,
+        None => get_visible_character(x, y),
+    }
 }
 
 pub fn set_color(x: usize, y: usize, ch: u8) {
-    unsafe { COLOR_BASEADDR.add(offset(x, y)).write_volatile(ch as u32) }
+    match scrollback::live_color(x, y) {
+        Some(cell) => *cell = ch,
+        None => set_visible_color(x, y, ch),
+    }
 }
 
 pub fn get_color(x: usize, y: usize) -> u8 {
-    unsafe { COLOR_BASEADDR.add(offset(x, y)).read_volatile() as u8 }
+    match scrollback::live_color(x, y) {
+        Some(cell) => *cell,
+        None => get_visible_color(x, y),
+    }
+}
+
+// what is on the screen, paged or not
+pub(crate) fn set_visible_character(x: usize, y: usize, ch: u8) {
+    match shadow(x, y) {
+        Some(framebuffer) => framebuffer.set_char(x, y, ch),
+        None => vga::set_character(x, y, ch),
+    }
+}
+
+pub(crate) fn get_visible_character(x: usize, y: usize) -> u8 {
+    match shadow(x, y) {
+        Some(framebuffer) => framebuffer.char(x, y),
+        None => vga::get_character(x, y),
+    }
+}
+
+pub(crate) fn set_visible_color(x: usize, y: usize, ch: u8) {
+    match shadow(x, y) {
+        Some(framebuffer) => framebuffer.set_color(x, y, ch),
+        None => vga::set_color(x, y, ch),
+    }
+}
+
+pub(crate) fn get_visible_color(x: usize, y: usize) -> u8 {
+    match shadow(x, y) {
+        Some(framebuffer) => framebuffer.color(x, y),
+        None => vga::get_color(x, y),
+    }
 }
 
 static mut SCREEN_X: usize = 0;
 static mut SCREEN_Y: usize = 0;
 static mut CURRENT_COLOR: u8 = 0;
+static mut BOLD: bool = false;
+static mut SAVED_CURSOR: (usize, usize) = (0, 0);
+static mut ANSI: Parser = Parser::new();
 
 pub mod monitor {
     use super::*;
@@ -106,6 +215,7 @@ pub mod monitor {
     }
 
     pub fn clear_screen() {
+        scrollback::leave();
         let color = get_color();
         for y in 0..SCREEN_HEIGHT {
             for x in 0..SCREEN_WIDTH {
@@ -117,6 +227,12 @@ pub mod monitor {
     }
 
     pub fn scroll_down() {
+        scrollback::leave();
+        scrollback::push_top_line();
+        if let Some(framebuffer) = unsafe { (*addr_of_mut!(SHADOW)).as_deref_mut() } {
+            framebuffer.scroll_up();
+            return;
+        }
         for y in 0..SCREEN_HEIGHT - 1 {
             for x in 0..SCREEN_WIDTH {
                 set_character(x, y, get_character(x, y + 1));
@@ -147,19 +263,157 @@ pub mod monitor {
         }
     }
 
-    #[allow(irrefutable_let_patterns)]
+    // understands the escape sequences of a VT100, so that colored output
+    // looks the same here and on the serial terminal
     pub fn putchar(ch: u8) {
-        match ch {
-            b'\r' => set_x(0),
-            b'\n' => newline(),
-            b'\x7f' => if let x = get_x() && x > 0 { set_x(x - 1) },
-            ch => {
-                let x = get_x();
-                let y = get_y();
-                set_character(x, y, ch);
-                super::set_color(x, y, get_color());
-                move_cursor_next();
+        scrollback::leave();
+        if let Some(action) = unsafe { (*addr_of_mut!(ANSI)).push(ch) } {
+            perform(action);
+        }
+    }
+
+    // draws any byte at the cursor, control characters included
+    pub fn put_glyph(ch: u8) {
+        let x = get_x();
+        let y = get_y();
+        set_character(x, y, ch);
+        super::set_color(x, y, get_color());
+        move_cursor_next();
+    }
+
+    fn perform(action: Action) {
+        match action {
+            Action::Print(ch) => put_glyph(ch),
+            Action::Control(b'\r') => set_x(0),
+            Action::Control(b'\n') => newline(),
+            Action::Control(b'\x08') => set_x(get_x().saturating_sub(1)),
+            Action::Control(b'\t') => {
+                set_x(((get_x() / TAB_WIDTH + 1) * TAB_WIDTH).min(SCREEN_WIDTH - 1))
+            }
+            Action::Escape(b'7') => save_cursor(),
+            Action::Escape(b'8') => restore_cursor(),
+            Action::Escape(b'c') => {
+                reset_attributes();
+                clear_screen();
             }
+            Action::Csi(csi) => perform_csi(&csi),
+            _ => {}
+        }
+    }
+
+    fn perform_csi(csi: &Csi) {
+        // e.g. hiding the cursor, there is none to hide
+        if csi.private {
+            return;
         }
+
+        let (x, y) = (get_x(), get_y());
+        let n = csi.param(0, 1) as usize;
+        let column = |n: usize| (n - 1).min(SCREEN_WIDTH - 1);
+        let row = |n: usize| (n - 1).min(SCREEN_HEIGHT - 1);
+        match csi.command {
+            b'A' => set_y(y.saturating_sub(n)),
+            b'B' => set_y((y + n).min(SCREEN_HEIGHT - 1)),
+            b'C' => set_x((x + n).min(SCREEN_WIDTH - 1)),
+            b'D' => set_x(x.saturating_sub(n)),
+            b'E' => set_xy(0, (y + n).min(SCREEN_HEIGHT - 1)),
+            b'F' => set_xy(0, y.saturating_sub(n)),
+            b'G' | b'`' => set_x(column(n)),
+            b'd' => set_y(row(n)),
+            b'H' | b'f' => set_xy(column(csi.param(1, 1) as usize), row(n)),
+            b'J' => {
+                let cursor = y * SCREEN_WIDTH + x;
+                match csi.param(0, 0) {
+                    0 => erase(cursor, SCREEN_WIDTH * SCREEN_HEIGHT),
+                    1 => erase(0, cursor + 1),
+                    2 | 3 => erase(0, SCREEN_WIDTH * SCREEN_HEIGHT),
+                    _ => {}
+                }
+            }
+            b'K' => {
+                let line = y * SCREEN_WIDTH;
+                match csi.param(0, 0) {
+                    0 => erase(line + x, line + SCREEN_WIDTH),
+                    1 => erase(line, line + x + 1),
+                    2 => erase(line, line + SCREEN_WIDTH),
+                    _ => {}
+                }
+            }
+            b'm' => select_graphic_rendition(csi.params()),
+            b's' => save_cursor(),
+            b'u' => restore_cursor(),
+            _ => {}
+        }
+    }
+
+    // cells are numbered row by row, erased cells keep the background color
+    fn erase(from: usize, to: usize) {
+        let color = get_color();
+        for i in from..to {
+            let (x, y) = (i % SCREEN_WIDTH, i / SCREEN_WIDTH);
+            set_character(x, y, b' ');
+            super::set_color(x, y, color);
+        }
+    }
+
+    pub fn get_attributes() -> Attributes {
+        Attributes {
+            color: get_color(),
+            bold: unsafe { BOLD },
+        }
+    }
+
+    pub fn set_attributes(attributes: Attributes) {
+        set_color(attributes.color);
+        unsafe { BOLD = attributes.bold }
+    }
+
+    fn reset_attributes() {
+        set_attributes(Attributes::DEFAULT);
+    }
+
+    fn select_graphic_rendition(params: &[u16]) {
+        let mut attributes = get_attributes();
+        attributes.apply(params);
+        set_attributes(attributes);
+    }
+
+    fn save_cursor() {
+        unsafe { SAVED_CURSOR = (get_x(), get_y()) }
+    }
+
+    fn restore_cursor() {
+        let (x, y) = unsafe { SAVED_CURSOR };
+        set_xy(x, y);
+    }
+}
+
+// writes at the cursor, like `monitor::putchar`, in the font's code page
+pub struct Screen;
+
+impl fmt::Write for Screen {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.chars()
+            .map(codepage::from_char)
+            .for_each(monitor::putchar);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host;
+    use core::fmt::Write;
+
+    #[test]
+    fn screen_code_page() {
+        host::reset();
+        monitor::set_xy(0, 0);
+        write!(Screen, "a│é").unwrap();
+        assert_eq!(host::screen_char(0, 0), b'a');
+        assert_eq!(host::screen_char(1, 0), codepage::VERTICAL);
+        assert_eq!(host::screen_char(2, 0), codepage::REPLACEMENT);
+        assert_eq!(monitor::get_x(), 3);
     }
 }
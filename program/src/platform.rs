@@ -0,0 +1,377 @@
+use core::time::Duration;
+
+use crate::board::{self, Button, Keyboard};
+use crate::monitor::{self, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::time::{Deadline, Instant};
+use crate::tube::{self, TubeMode};
+use crate::uart;
+
+// a grid of character cells, each with a color byte (background << 4 | foreground)
+pub trait TextDisplay {
+    fn size(&self) -> (usize, usize);
+
+    fn set_cell(&mut self, x: usize, y: usize, ch: u8, color: u8);
+
+    fn cell(&self, x: usize, y: usize) -> (u8, u8);
+
+    fn clear(&mut self, color: u8) {
+        let (width, height) = self.size();
+        for y in 0..height {
+            for x in 0..width {
+                self.set_cell(x, y, b' ', color);
+            }
+        }
+    }
+
+    // clipped at the right edge, returns the number of cells written
+    fn put_str(&mut self, x: usize, y: usize, s: &[u8], color: u8) -> usize {
+        let (width, height) = self.size();
+        if y >= height || x >= width {
+            return 0;
+        }
+        let len = s.len().min(width - x);
+        for (i, ch) in s[..len].iter().enumerate() {
+            self.set_cell(x + i, y, *ch, color);
+        }
+        len
+    }
+
+    // shows what was drawn, for displays that buffer it
+    fn flush(&mut self) {}
+}
+
+// Everything a program needs from the board. Programs generic over it can
+// run on the real board (`Board`), under `Recorder`/`Replay`, or against a
+// test double.
+pub trait Platform: TextDisplay {
+    fn uart_read(&mut self) -> Option<u8>;
+
+    // blocks until there is room
+    fn uart_write(&mut self, data: u8);
+
+    fn set_tube(&mut self, mode: TubeMode, value: u32, enable: u8);
+
+    // bit `i` is LED `i`
+    fn write_leds(&mut self, mask: u32);
+
+    // bit `i` is switch `i`
+    fn read_switches(&mut self) -> u32;
+
+    fn read_button(&mut self, button: Button) -> bool;
+
+    fn read_key(&mut self) -> Option<Keyboard>;
+
+    fn now(&mut self) -> Instant;
+
+    fn random(&mut self) -> u32;
+
+    // background work, e.g. draining the UART, called while waiting
+    fn poll(&mut self) {}
+
+    fn uart_write_all(&mut self, data: &[u8]) {
+        data.iter().for_each(|b| self.uart_write(*b));
+    }
+
+    // adds up the time between readings of `now`, which may wrap, so a
+    // step across the wrap counts for nothing rather than waiting forever
+    fn delay(&mut self, duration: Duration) {
+        let mut last = self.now();
+        let mut elapsed = Duration::ZERO;
+        while elapsed < duration {
+            self.poll();
+            let now = self.now();
+            elapsed += now.saturating_duration_since(last);
+            last = now;
+        }
+    }
+}
+
+// the real board, through the MMIO drivers
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Board;
+
+impl TextDisplay for Board {
+    fn size(&self) -> (usize, usize) {
+        (SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, ch: u8, color: u8) {
+        monitor::set_character(x, y, ch);
+        monitor::set_color(x, y, color);
+    }
+
+    fn cell(&self, x: usize, y: usize) -> (u8, u8) {
+        (monitor::get_character(x, y), monitor::get_color(x, y))
+    }
+
+    fn flush(&mut self) {
+        monitor::flush()
+    }
+}
+
+impl Platform for Board {
+    fn uart_read(&mut self) -> Option<u8> {
+        uart::try_read()
+    }
+
+    fn uart_write(&mut self, data: u8) {
+        uart::write(data)
+    }
+
+    fn set_tube(&mut self, mode: TubeMode, value: u32, enable: u8) {
+        tube::set_tube_mode(mode);
+        tube::set_tube_value(value);
+        tube::set_tube_enable(enable);
+    }
+
+    fn write_leds(&mut self, mask: u32) {
+        board::write_leds(mask)
+    }
+
+    fn read_switches(&mut self) -> u32 {
+        board::read_switches()
+    }
+
+    fn read_button(&mut self, button: Button) -> bool {
+        board::read_button(button)
+    }
+
+    fn read_key(&mut self) -> Option<Keyboard> {
+        // invalid codes are dropped
+        board::try_read_keyboard().and_then(Result::ok)
+    }
+
+    fn now(&mut self) -> Instant {
+        Instant::now()
+    }
+
+    fn random(&mut self) -> u32 {
+        board::random_value()
+    }
+
+    fn poll(&mut self) {
+        uart::poll()
+    }
+
+    fn delay(&mut self, duration: Duration) {
+        let deadline = Deadline::after(duration);
+        while !deadline.is_expired() {
+            self.poll();
+        }
+    }
+}
+
+// the result of every input read from a platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Uart(Option<u8>),
+    Switches(u32),
+    Button(Button, bool),
+    Key(Option<Keyboard>),
+    Now(Instant),
+    Random(u32),
+}
+
+// passes everything through, reporting each input to `sink`
+pub struct Recorder<P, F> {
+    inner: P,
+    sink: F,
+}
+
+impl<P: Platform, F: FnMut(Input)> Recorder<P, F> {
+    pub fn new(inner: P, sink: F) -> Recorder<P, F> {
+        Recorder { inner, sink }
+    }
+
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+
+    fn record<T>(&mut self, value: T, input: impl FnOnce(T) -> Input) -> T
+    where
+        T: Copy,
+    {
+        (self.sink)(input(value));
+        value
+    }
+}
+
+impl<P: Platform, F> TextDisplay for Recorder<P, F> {
+    fn size(&self) -> (usize, usize) {
+        self.inner.size()
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, ch: u8, color: u8) {
+        self.inner.set_cell(x, y, ch, color)
+    }
+
+    fn cell(&self, x: usize, y: usize) -> (u8, u8) {
+        self.inner.cell(x, y)
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush()
+    }
+}
+
+impl<P: Platform, F: FnMut(Input)> Platform for Recorder<P, F> {
+    fn uart_read(&mut self) -> Option<u8> {
+        let value = self.inner.uart_read();
+        self.record(value, Input::Uart)
+    }
+
+    fn uart_write(&mut self, data: u8) {
+        self.inner.uart_write(data)
+    }
+
+    fn set_tube(&mut self, mode: TubeMode, value: u32, enable: u8) {
+        self.inner.set_tube(mode, value, enable)
+    }
+
+    fn write_leds(&mut self, mask: u32) {
+        self.inner.write_leds(mask)
+    }
+
+    fn read_switches(&mut self) -> u32 {
+        let value = self.inner.read_switches();
+        self.record(value, Input::Switches)
+    }
+
+    fn read_button(&mut self, button: Button) -> bool {
+        let value = self.inner.read_button(button);
+        self.record(value, |v| Input::Button(button, v))
+    }
+
+    fn read_key(&mut self) -> Option<Keyboard> {
+        let value = self.inner.read_key();
+        self.record(value, Input::Key)
+    }
+
+    fn now(&mut self) -> Instant {
+        let value = self.inner.now();
+        self.record(value, Input::Now)
+    }
+
+    fn random(&mut self) -> u32 {
+        let value = self.inner.random();
+        self.record(value, Input::Random)
+    }
+
+    fn poll(&mut self) {
+        self.inner.poll()
+    }
+}
+
+// takes inputs from a recording, and sends outputs to `inner`
+// panics if the program asks for another kind of input than was recorded
+pub struct Replay<P, I> {
+    inner: P,
+    inputs: I,
+}
+
+impl<P: Platform, I: Iterator<Item = Input>> Replay<P, I> {
+    pub fn new(inner: P, inputs: I) -> Replay<P, I> {
+        Replay { inner, inputs }
+    }
+
+    pub fn into_inner(self) -> P {
+        self.inner
+    }
+
+    fn next(&mut self) -> Input {
+        self.inputs.next().expect("replay: recording exhausted")
+    }
+}
+
+impl<P: Platform, I> TextDisplay for Replay<P, I> {
+    fn size(&self) -> (usize, usize) {
+        self.inner.size()
+    }
+
+    fn set_cell(&mut self, x: usize, y: usize, ch: u8, color: u8) {
+        self.inner.set_cell(x, y, ch, color)
+    }
+
+    fn cell(&self, x: usize, y: usize) -> (u8, u8) {
+        self.inner.cell(x, y)
+    }
+
+    fn flush(&mut self) {
+        self.inner.flush()
+    }
+}
+
+impl<P: Platform, I: Iterator<Item = Input>> Platform for Replay<P, I> {
+    fn uart_read(&mut self) -> Option<u8> {
+        match self.next() {
+            Input::Uart(value) => value,
+            input => panic!("replay: expected UART input, found {:?}", input),
+        }
+    }
+
+    fn uart_write(&mut self, data: u8) {
+        self.inner.uart_write(data)
+    }
+
+    fn set_tube(&mut self, mode: TubeMode, value: u32, enable: u8) {
+        self.inner.set_tube(mode, value, enable)
+    }
+
+    fn write_leds(&mut self, mask: u32) {
+        self.inner.write_leds(mask)
+    }
+
+    fn read_switches(&mut self) -> u32 {
+        match self.next() {
+            Input::Switches(value) => value,
+            input => panic!("replay: expected switches, found {:?}", input),
+        }
+    }
+
+    fn read_button(&mut self, button: Button) -> bool {
+        match self.next() {
+            Input::Button(b, value) if b == button => value,
+            input => panic!("replay: expected button {:?}, found {:?}", button, input),
+        }
+    }
+
+    fn read_key(&mut self) -> Option<Keyboard> {
+        match self.next() {
+            Input::Key(value) => value,
+            input => panic!("replay: expected keypad input, found {:?}", input),
+        }
+    }
+
+    fn now(&mut self) -> Instant {
+        match self.next() {
+            Input::Now(value) => value,
+            input => panic!("replay: expected time, found {:?}", input),
+        }
+    }
+
+    fn random(&mut self) -> u32 {
+        match self.next() {
+            Input::Random(value) => value,
+            input => panic!("replay: expected random value, found {:?}", input),
+        }
+    }
+
+    fn poll(&mut self) {
+        self.inner.poll()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host;
+
+    #[test]
+    fn delay_across_a_wrap() {
+        host::reset();
+        host::with_board(|board| board.cycles = (1 << 32) - 1000);
+        Board.delay(Duration::from_millis(1));
+        let cycles = host::with_board(|board| board.cycles) - ((1 << 32) - 1000);
+        assert!((10_000..11_000).contains(&cycles));
+    }
+}
@@ -1,6 +1,11 @@
 pub use crate::board::*;
+pub use crate::button::{ButtonConfig, ButtonEvent, ButtonEventKind, ButtonEvents};
 pub use crate::console::*;
+pub use crate::keypad::{AutoRepeat, KeyEvent, KeyEventKind, Keypad, KeypadConfig};
+pub use crate::led_effects::{Animation, Effect, LedEffects};
 pub use crate::monitor::monitor;
+pub use crate::platform::{Board, Platform, TextDisplay};
+pub use crate::scan::{List, Word};
 pub use crate::time::*;
 pub use crate::tube::*;
 pub use crate::uart;
@@ -0,0 +1,110 @@
+// The P20 reset button clears most registers and jumps back to the loader,
+// but keeps the RAM. Variables in the `.retained` section are neither loaded
+// nor cleared at startup, so they survive a reset, and a magic value there
+// tells a cold boot apart from a reset.
+
+use core::mem::{size_of, MaybeUninit};
+
+use crate::mmio::INTERRUPT_PC;
+
+const MAGIC: u32 = 0x5EE7_B007;
+
+#[link_section = ".retained"]
+static mut BOOT_MAGIC: u32 = 0;
+
+static mut CAUSE: ResetCause = ResetCause::ColdBoot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetCause {
+    // power on, or the RAM held someone else's data
+    ColdBoot,
+    // the reset button, `pc` is where the program was when it was pressed
+    Reset { pc: u32 },
+}
+
+// the PC before the last interrupt or reset
+pub fn interrupt_pc() -> u32 {
+    INTERRUPT_PC.read()
+}
+
+// called once at startup, after the BSS is cleared
+pub fn init() {
+    unsafe {
+        CAUSE = if BOOT_MAGIC == MAGIC {
+            ResetCause::Reset { pc: interrupt_pc() }
+        } else {
+            ResetCause::ColdBoot
+        };
+        BOOT_MAGIC = MAGIC;
+    }
+}
+
+pub fn cause() -> ResetCause {
+    unsafe { CAUSE }
+}
+
+pub fn is_warm() -> bool {
+    cause() != ResetCause::ColdBoot
+}
+
+// the next start will be reported as a cold boot
+pub fn forget() {
+    unsafe { BOOT_MAGIC = 0 }
+}
+
+// A value kept across the reset button. Put it in the retained section:
+//
+//     #[link_section = ".retained"]
+//     static mut SAVED: Retained<State> = Retained::new();
+//
+// It is only valid after `set`, and never after a cold boot.
+#[repr(C)]
+pub struct Retained<T> {
+    magic: u32,
+    value: MaybeUninit<T>,
+}
+
+impl<T: Copy> Retained<T> {
+    // also covers the size, so that a different layout is not mistaken for ours
+    const MAGIC: u32 = MAGIC ^ size_of::<T>() as u32;
+
+    pub const fn new() -> Retained<T> {
+        Retained {
+            magic: 0,
+            value: MaybeUninit::uninit(),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        is_warm() && self.magic == Self::MAGIC
+    }
+
+    pub fn get(&self) -> Option<T> {
+        if self.is_valid() {
+            Some(unsafe { self.value.assume_init() })
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, value: T) {
+        self.value = MaybeUninit::new(value);
+        self.magic = Self::MAGIC;
+    }
+
+    pub fn take(&mut self) -> Option<T> {
+        let value = self.get();
+        self.clear();
+        value
+    }
+
+    pub fn clear(&mut self) {
+        self.magic = 0;
+    }
+}
+
+impl<T: Copy> Default for Retained<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
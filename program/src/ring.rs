@@ -0,0 +1,116 @@
+use core::mem::MaybeUninit;
+
+// fixed-capacity FIFO queue, usable in `static`s
+pub struct RingBuffer<T: Copy, const N: usize> {
+    buf: [MaybeUninit<T>; N],
+    head: usize,
+    len: usize,
+}
+
+impl<T: Copy, const N: usize> RingBuffer<T, N> {
+    pub const fn new() -> RingBuffer<T, N> {
+        RingBuffer {
+            // an array of `MaybeUninit` needs no initialization
+            buf: unsafe { MaybeUninit::<[MaybeUninit<T>; N]>::uninit().assume_init() },
+            head: 0,
+            len: 0,
+        }
+    }
+
+    #[inline]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.len = 0;
+    }
+
+    #[inline]
+    fn index(&self, offset: usize) -> usize {
+        (self.head + offset) % N
+    }
+
+    // returns the value back if the buffer is full
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(value);
+        }
+        let idx = self.index(self.len);
+        self.buf[idx].write(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    // drops (and returns) the oldest value if the buffer is full
+    pub fn push_overwrite(&mut self, value: T) -> Option<T> {
+        let dropped = if self.is_full() { self.pop() } else { None };
+        let _ = self.push(value);
+        dropped
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        let value = unsafe { self.buf[self.head].assume_init() };
+        self.head = self.index(1);
+        self.len -= 1;
+        Some(value)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        self.len -= 1;
+        Some(unsafe { self.buf[self.index(self.len)].assume_init() })
+    }
+
+    // `index` counts from the oldest value
+    pub fn get(&self, index: usize) -> Option<T> {
+        if index < self.len {
+            Some(unsafe { self.buf[self.index(index)].assume_init() })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_ref(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            Some(unsafe { self.buf[self.index(index)].assume_init_ref() })
+        } else {
+            None
+        }
+    }
+
+    pub fn peek(&self) -> Option<T> {
+        self.get(0)
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = T> + '_ {
+        (0..self.len).map(|i| unsafe { self.buf[self.index(i)].assume_init() })
+    }
+}
+
+impl<T: Copy, const N: usize> Default for RingBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,6 +1,7 @@
 use rand::{Error, RngCore};
 
 use crate::board::random_value;
+use crate::platform::Platform;
 
 pub struct FpgaRng;
 
@@ -8,6 +9,27 @@ pub fn rng() -> FpgaRng {
     FpgaRng
 }
 
+// the entropy source of a `Platform`
+pub struct PlatformRng<'a, P>(pub &'a mut P);
+
+fn next_u64(mut next_u32: impl FnMut() -> u32) -> u64 {
+    let v1 = next_u32();
+    let v2 = next_u32() ^ next_u32();
+    (v1 as u64) << 32 | (v2 as u64)
+}
+
+fn fill_bytes(mut next_u32: impl FnMut() -> u32, dest: &mut [u8]) {
+    dest.iter_mut().for_each(|ptr| {
+        let v = next_u32();
+        let p1 = (v >> 24) & 0x3;
+        let p2 = (v >> 16) & 0x3;
+        let p3 = (v >> 8) & 0x3;
+        let p4 = v & 0x3;
+        let v = (p1 << 6) | (p2 << 4) | (p3 << 2) | p4;
+        *ptr = v as u8;
+    });
+}
+
 impl RngCore for FpgaRng {
     #[inline]
     fn next_u32(&mut self) -> u32 {
@@ -15,21 +37,31 @@ impl RngCore for FpgaRng {
     }
 
     fn next_u64(&mut self) -> u64 {
-        let v1 = self.next_u32();
-        let v2 = self.next_u32() ^ self.next_u32();
-        (v1 as u64) << 32 | (v2 as u64)
+        next_u64(random_value)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        fill_bytes(random_value, dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl<P: Platform> RngCore for PlatformRng<'_, P> {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        self.0.random()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        next_u64(|| self.0.random())
     }
 
     fn fill_bytes(&mut self, dest: &mut [u8]) {
-        dest.iter_mut().for_each(|ptr| {
-            let v = self.next_u32();
-            let p1 = (v >> 24) & 0x3;
-            let p2 = (v >> 16) & 0x3;
-            let p3 = (v >> 8) & 0x3;
-            let p4 = (v >> 0) & 0x3;
-            let v = (p1 << 6) | (p2 << 4) | (p3 << 2) | p4;
-            *ptr = v as u8;
-        });
+        fill_bytes(|| self.0.random(), dest)
     }
 
     fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
@@ -0,0 +1,406 @@
+// Typed input, asked again until it parses:
+//
+//   let n = scan!("n: ", i32);
+//   let (addr, len) = scan!("addr len: ", usize, u8);
+//   let list = scan!("numbers: ", List<u32, 8>);
+//   let n = scan!(keypad: &mut keypad, "n: ", u32);
+//
+// With several types, the line is split at whitespace. Integers can be
+// written in hex with `0x`, lists are separated by commas.
+//
+// On the keypad, `#` ends the entry, `*` is the minus sign, `A` a comma
+// and `B` deletes the last character.
+
+use core::fmt;
+use core::ops::Deref;
+use core::str::SplitWhitespace;
+
+use crate::board::Keyboard;
+use crate::console::{eprint, print, print_str, read_line};
+use crate::keypad::{KeyEventKind, Keypad};
+
+pub const LINE_MAX: usize = 64;
+pub const WORD_MAX: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanError {
+    // fewer values than asked for
+    Missing,
+    // more values than asked for
+    Extra,
+    // a word or a list that does not fit
+    TooLong,
+    // what was expected instead
+    Invalid(&'static str),
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::Missing => f.write_str("not enough values"),
+            ScanError::Extra => f.write_str("too many values"),
+            ScanError::TooLong => f.write_str("too long"),
+            ScanError::Invalid(expected) => write!(f, "expected {}", expected),
+        }
+    }
+}
+
+pub trait Scan: Sized {
+    fn scan(s: &str) -> Result<Self, ScanError>;
+}
+
+fn split_radix(s: &str) -> (&str, u32) {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => (hex, 16),
+        None => (s, 10),
+    }
+}
+
+macro_rules! impl_scan_unsigned {
+    ($($ty: ty),*) => {
+        $(impl Scan for $ty {
+            fn scan(s: &str) -> Result<Self, ScanError> {
+                let (digits, radix) = split_radix(s.strip_prefix('+').unwrap_or(s));
+                // `from_str_radix` would take a second sign
+                if digits.starts_with(['+', '-']) {
+                    return Err(ScanError::Invalid("an unsigned integer"));
+                }
+                <$ty>::from_str_radix(digits, radix)
+                    .map_err(|_| ScanError::Invalid(concat!("an integer in the range of ", stringify!($ty))))
+            }
+        })*
+    };
+}
+
+macro_rules! impl_scan_signed {
+    ($($ty: ty),*) => {
+        $(impl Scan for $ty {
+            fn scan(s: &str) -> Result<Self, ScanError> {
+                let (negative, rest) = match s.strip_prefix('-') {
+                    Some(rest) => (true, rest),
+                    None => (false, s.strip_prefix('+').unwrap_or(s)),
+                };
+                let (digits, radix) = split_radix(rest);
+                if digits.starts_with(['+', '-']) {
+                    return Err(ScanError::Invalid("an integer"));
+                }
+                let error = ScanError::Invalid(concat!("an integer in the range of ", stringify!($ty)));
+                let magnitude = u64::from_str_radix(digits, radix).map_err(|_| error)?;
+                let value = match negative {
+                    true if magnitude <= 1 << 63 => (magnitude as i64).wrapping_neg(),
+                    false if magnitude <= i64::MAX as u64 => magnitude as i64,
+                    _ => return Err(error),
+                };
+                <$ty>::try_from(value).map_err(|_| error)
+            }
+        })*
+    };
+}
+
+impl_scan_unsigned!(u8, u16, u32, u64, usize);
+impl_scan_signed!(i8, i16, i32, i64, isize);
+
+impl Scan for bool {
+    fn scan(s: &str) -> Result<Self, ScanError> {
+        match s {
+            "1" | "true" | "yes" | "y" | "on" => Ok(true),
+            "0" | "false" | "no" | "n" | "off" => Ok(false),
+            _ => Err(ScanError::Invalid("true or false")),
+        }
+    }
+}
+
+// a string without whitespace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Word {
+    buf: [u8; WORD_MAX],
+    len: usize,
+}
+
+impl Deref for Word {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        // copied from a `&str`
+        unsafe { core::str::from_utf8_unchecked(&self.buf[..self.len]) }
+    }
+}
+
+impl fmt::Display for Word {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self)
+    }
+}
+
+impl Scan for Word {
+    fn scan(s: &str) -> Result<Self, ScanError> {
+        if s.is_empty() || s.contains(char::is_whitespace) {
+            return Err(ScanError::Invalid("a single word"));
+        }
+        if s.len() > WORD_MAX {
+            return Err(ScanError::TooLong);
+        }
+        let mut word = Word::default();
+        word.buf[..s.len()].copy_from_slice(s.as_bytes());
+        word.len = s.len();
+        Ok(word)
+    }
+}
+
+// comma separated values, at most `N`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct List<T, const N: usize> {
+    items: [T; N],
+    len: usize,
+}
+
+impl<T, const N: usize> Deref for List<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items[..self.len]
+    }
+}
+
+impl<T: Scan + Copy + Default, const N: usize> Scan for List<T, N> {
+    fn scan(s: &str) -> Result<Self, ScanError> {
+        let mut list = List {
+            items: [T::default(); N],
+            len: 0,
+        };
+        if s.trim().is_empty() {
+            return Ok(list);
+        }
+        for item in s.split(',') {
+            if list.len == N {
+                return Err(ScanError::TooLong);
+            }
+            list.items[list.len] = T::scan(item.trim())?;
+            list.len += 1;
+        }
+        Ok(list)
+    }
+}
+
+// used by `scan!` for each of several values
+pub fn field<T: Scan>(fields: &mut SplitWhitespace) -> Result<T, ScanError> {
+    T::scan(fields.next().ok_or(ScanError::Missing)?)
+}
+
+// used by `scan!` after the last of several values
+pub fn finish(mut fields: SplitWhitespace) -> Result<(), ScanError> {
+    match fields.next() {
+        Some(_) => Err(ScanError::Extra),
+        None => Ok(()),
+    }
+}
+
+pub enum Source<'a> {
+    Uart,
+    Keypad(&'a mut Keypad),
+}
+
+// keeps the keys pressed ahead, and leaves out auto-repeats
+fn next_key(keypad: &mut Keypad) -> Keyboard {
+    loop {
+        match keypad.poll() {
+            Some(event) if event.kind == KeyEventKind::Down => return event.key,
+            _ => {}
+        }
+    }
+}
+
+fn read_keypad<'a>(keypad: &mut Keypad, buf: &'a mut [u8]) -> &'a str {
+    let mut len = 0;
+    loop {
+        let ch = match next_key(keypad) {
+            Keyboard::Number => break,
+            Keyboard::Star => b'-',
+            Keyboard::A => b',',
+            Keyboard::B => {
+                if len > 0 {
+                    len -= 1;
+                    print_str("\x08 \x08");
+                }
+                continue;
+            }
+            key => match key.as_number() {
+                Some(digit) => b'0' + digit as u8,
+                None => continue,
+            },
+        };
+        if len < buf.len() {
+            buf[len] = ch;
+            len += 1;
+            print(format_args!("{}", ch as char));
+        }
+    }
+    print_str("\r\n");
+    // only ASCII was put in
+    unsafe { core::str::from_utf8_unchecked(&buf[..len]) }
+}
+
+// reads lines from `source` until `parse` takes one
+pub fn scan_with<T>(
+    source: &mut Source,
+    prompt: &str,
+    parse: impl Fn(&str) -> Result<T, ScanError>,
+) -> T {
+    let mut buf = [0; LINE_MAX];
+    loop {
+        print_str(prompt);
+        let line = match source {
+            Source::Uart => read_line(&mut buf),
+            Source::Keypad(keypad) => read_keypad(keypad, &mut buf),
+        };
+        match parse(line.trim()) {
+            Ok(value) => return value,
+            Err(err) => eprint(format_args!("Invalid input, {}, try again\r\n", err)),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! scan {
+    (keypad: $keypad: expr, $prompt: expr, $($ty: ty),+) => {
+        $crate::scan::scan_with(
+            &mut $crate::scan::Source::Keypad($keypad),
+            $prompt,
+            |line| $crate::scan!(@parse line; $($ty),+),
+        )
+    };
+    (@parse $line: ident; $ty: ty) => {
+        <$ty as $crate::scan::Scan>::scan($line)
+    };
+    (@parse $line: ident; $($ty: ty),+) => {
+        (|| {
+            let mut fields = $line.split_whitespace();
+            let value = ($($crate::scan::field::<$ty>(&mut fields)?,)+);
+            $crate::scan::finish(fields).map(|_| value)
+        })()
+    };
+    ($prompt: literal, $($ty: ty),+) => {
+        $crate::scan::scan_with(
+            &mut $crate::scan::Source::Uart,
+            $prompt,
+            |line| $crate::scan!(@parse line; $($ty),+),
+        )
+    };
+    ($($ty: ty),+) => {
+        $crate::scan!("", $($ty),+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::{set_sinks, Sinks, Stream};
+    use crate::host::{self, push_uart_input, take_uart_output};
+    use crate::keypad::{AutoRepeat, KeypadConfig};
+    use crate::uart;
+    use core::time::Duration;
+
+    fn parse2(line: &str) -> Result<(u8, i32), ScanError> {
+        crate::scan!(@parse line; u8, i32)
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(u8::scan("255"), Ok(255));
+        assert_eq!(u8::scan("+0xff"), Ok(255));
+        assert_eq!(u32::scan("0XdeadBEEF"), Ok(0xDEADBEEF));
+        assert_eq!(i8::scan("-128"), Ok(-128));
+        assert_eq!(i8::scan("-0x80"), Ok(-128));
+        assert_eq!(i64::scan("-9223372036854775808"), Ok(i64::MIN));
+        assert_eq!(i64::scan("9223372036854775807"), Ok(i64::MAX));
+
+        // overflow
+        assert!(u8::scan("256").is_err());
+        assert!(i8::scan("128").is_err());
+        assert!(i8::scan("-129").is_err());
+        assert!(i64::scan("9223372036854775808").is_err());
+        assert!(i64::scan("-9223372036854775809").is_err());
+        assert!(u64::scan("18446744073709551616").is_err());
+
+        for bad in ["", "-", "0x", "--1", "+-1", "-+1", "1.5", "12a", " 1"] {
+            assert!(i32::scan(bad).is_err(), "{:?}", bad);
+        }
+        assert_eq!(
+            u8::scan("-1"),
+            Err(ScanError::Invalid("an unsigned integer"))
+        );
+        assert!(u8::scan("++1").is_err());
+    }
+
+    #[test]
+    fn words_and_lists() {
+        assert!(bool::scan("yes").unwrap());
+        assert!(bool::scan("maybe").is_err());
+
+        assert_eq!(&*Word::scan("hello").unwrap(), "hello");
+        assert_eq!(
+            Word::scan(&"x".repeat(WORD_MAX)).map(|w| w.len()),
+            Ok(WORD_MAX)
+        );
+        assert_eq!(
+            Word::scan(&"x".repeat(WORD_MAX + 1)),
+            Err(ScanError::TooLong)
+        );
+        assert!(Word::scan("").is_err());
+
+        assert_eq!(&*List::<u8, 3>::scan("1, 2,3").unwrap(), [1, 2, 3]);
+        assert!(List::<u8, 3>::scan("  ").unwrap().is_empty());
+        assert_eq!(List::<u8, 3>::scan("1,2,3,4"), Err(ScanError::TooLong));
+        assert!(List::<u8, 3>::scan("1,,3").is_err());
+        assert!(List::<u8, 3>::scan("1,300").is_err());
+    }
+
+    #[test]
+    fn fields() {
+        assert_eq!(parse2("7 -8"), Ok((7, -8)));
+        assert_eq!(parse2("  7\t-8 "), Ok((7, -8)));
+        assert_eq!(parse2("7"), Err(ScanError::Missing));
+        assert_eq!(parse2(""), Err(ScanError::Missing));
+        assert_eq!(parse2("7 8 9"), Err(ScanError::Extra));
+        assert!(parse2("300 8").is_err());
+    }
+
+    #[test]
+    fn keypad_typed_ahead() {
+        host::reset();
+        let mut keypad = Keypad::with_config(KeypadConfig {
+            hold_time: Duration::from_millis(300),
+            repeat: Some(AutoRepeat {
+                delay: Duration::from_millis(100),
+                interval: Duration::from_millis(50),
+            }),
+        });
+        host::push_key(Keyboard::_1);
+        keypad.update();
+        // repeats the 1 a few times
+        host::advance(Duration::from_millis(400));
+        host::push_key(Keyboard::_2);
+        host::push_key(Keyboard::Number);
+        keypad.update();
+        assert_eq!(crate::scan!(keypad: &mut keypad, "", i32), 12);
+    }
+
+    #[test]
+    fn asks_again() {
+        host::reset();
+        uart::discard_input();
+        set_sinks(Stream::Stdout, Sinks::UART);
+        set_sinks(Stream::Stderr, Sinks::UART);
+
+        push_uart_input(b"1 2 3\r999999999999\r-5 0x10\r");
+        assert_eq!(crate::scan!("> ", i32, u8), (-5, 16));
+        let output = take_uart_output();
+        let output = core::str::from_utf8(&output).unwrap();
+        assert_eq!(output.matches("> ").count(), 3);
+        assert!(output.contains("Invalid input, too many values, try again"));
+        assert!(output.contains("Invalid input, expected an integer in the range of i32"));
+
+        set_sinks(Stream::Stdout, Sinks::UART | Sinks::SCREEN);
+        set_sinks(Stream::Stderr, Sinks::UART | Sinks::SCREEN);
+    }
+}
@@ -0,0 +1,275 @@
+// Keeps the lines that scroll off the top of the screen, and pages back
+// through them:
+//
+//   static mut SCROLLBACK: Scrollback<200> = Scrollback::new();
+//   scrollback::install(unsafe { &mut *addr_of_mut!(SCROLLBACK) });
+//
+// The Up and Down buttons page by half a screen, keypad A and B by a line,
+// and D goes back. New output on the console goes back too, while cells
+// drawn directly (`Window`, `tui`, ...) are kept aside until then. After a
+// panic, `pager` keeps paging until reset, if a scrollback is installed.
+
+use core::ptr::addr_of_mut;
+
+use crate::board::{Button, Keyboard};
+use crate::button::{ButtonEventKind, ButtonEvents};
+use crate::keypad::{KeyEventKind, Keypad};
+use crate::monitor::{self, SCREEN_HEIGHT, SCREEN_WIDTH};
+
+#[derive(Clone, Copy)]
+pub struct Line {
+    chars: [u8; SCREEN_WIDTH],
+    colors: [u8; SCREEN_WIDTH],
+}
+
+impl Line {
+    const BLANK: Line = Line {
+        chars: [b' '; SCREEN_WIDTH],
+        colors: [0; SCREEN_WIDTH],
+    };
+
+    fn load(&mut self, y: usize) {
+        for x in 0..SCREEN_WIDTH {
+            self.chars[x] = monitor::get_visible_character(x, y);
+            self.colors[x] = monitor::get_visible_color(x, y);
+        }
+    }
+
+    fn draw(&self, y: usize) {
+        for x in 0..SCREEN_WIDTH {
+            monitor::set_visible_character(x, y, self.chars[x]);
+            monitor::set_visible_color(x, y, self.colors[x]);
+        }
+    }
+}
+
+// the storage, `N` lines of history
+pub struct Scrollback<const N: usize> {
+    history: [Line; N],
+    // the screen as it was when paging started
+    live: [Line; SCREEN_HEIGHT],
+}
+
+impl<const N: usize> Scrollback<N> {
+    pub const fn new() -> Scrollback<N> {
+        Scrollback {
+            history: [Line::BLANK; N],
+            live: [Line::BLANK; SCREEN_HEIGHT],
+        }
+    }
+}
+
+impl<const N: usize> Default for Scrollback<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct State {
+    history: &'static mut [Line],
+    live: &'static mut [Line; SCREEN_HEIGHT],
+    // the oldest line
+    head: usize,
+    len: usize,
+    // lines paged back, 0 shows the live screen
+    offset: usize,
+}
+
+static mut STATE: Option<State> = None;
+
+fn state() -> Option<&'static mut State> {
+    unsafe { (*addr_of_mut!(STATE)).as_mut() }
+}
+
+pub fn install<const N: usize>(scrollback: &'static mut Scrollback<N>) {
+    leave();
+    unsafe {
+        STATE = Some(State {
+            history: &mut scrollback.history,
+            live: &mut scrollback.live,
+            head: 0,
+            len: 0,
+            offset: 0,
+        })
+    }
+}
+
+pub fn uninstall() {
+    leave();
+    unsafe { STATE = None }
+}
+
+pub fn is_installed() -> bool {
+    state().is_some()
+}
+
+// called by `monitor` before the top line scrolls away
+pub(crate) fn push_top_line() {
+    let Some(state) = state() else { return };
+    let capacity = state.history.len();
+    if capacity == 0 {
+        return;
+    }
+    let index = (state.head + state.len) % capacity;
+    state.history[index].load(0);
+    if state.len < capacity {
+        state.len += 1;
+    } else {
+        state.head = (state.head + 1) % capacity;
+    }
+}
+
+// a line of the live screen, while it is kept aside
+fn live_line(x: usize, y: usize) -> Option<&'static mut Line> {
+    let state = state().filter(|state| state.offset > 0)?;
+    (x < SCREEN_WIDTH).then(|| state.live.get_mut(y))?
+}
+
+pub(crate) fn live_character(x: usize, y: usize) -> Option<&'static mut u8> {
+    live_line(x, y).map(|line| &mut line.chars[x])
+}
+
+pub(crate) fn live_color(x: usize, y: usize) -> Option<&'static mut u8> {
+    live_line(x, y).map(|line| &mut line.colors[x])
+}
+
+pub fn len() -> usize {
+    state().map_or(0, |state| state.len)
+}
+
+pub fn offset() -> usize {
+    state().map_or(0, |state| state.offset)
+}
+
+pub fn is_paging() -> bool {
+    offset() > 0
+}
+
+fn draw(state: &State) {
+    // history, then the live screen
+    let top = state.len - state.offset;
+    for y in 0..SCREEN_HEIGHT {
+        let line = top + y;
+        if line < state.len {
+            let capacity = state.history.len();
+            state.history[(state.head + line) % capacity].draw(y);
+        } else {
+            state.live[line - state.len].draw(y);
+        }
+    }
+    monitor::flush();
+}
+
+// shows the screen `offset` lines back, clamped to the history
+pub fn scroll_to(offset: usize) {
+    let Some(state) = state() else { return };
+    let offset = offset.min(state.len);
+    if offset == state.offset {
+        return;
+    }
+    if state.offset == 0 {
+        for (y, line) in state.live.iter_mut().enumerate() {
+            line.load(y);
+        }
+    }
+    state.offset = offset;
+    draw(state);
+}
+
+pub fn page_up(lines: usize) {
+    scroll_to(offset().saturating_add(lines));
+}
+
+pub fn page_down(lines: usize) {
+    scroll_to(offset().saturating_sub(lines));
+}
+
+// back to the live screen
+pub fn leave() {
+    scroll_to(0);
+}
+
+// returns whether the button was used
+pub fn handle_button(button: Button) -> bool {
+    match button {
+        Button::Up => page_up(SCREEN_HEIGHT / 2),
+        Button::Down => page_down(SCREEN_HEIGHT / 2),
+        _ => return false,
+    }
+    true
+}
+
+// returns whether the key was used
+pub fn handle_key(key: Keyboard) -> bool {
+    match key {
+        Keyboard::A => page_up(1),
+        Keyboard::B => page_down(1),
+        Keyboard::D => leave(),
+        _ => return false,
+    }
+    true
+}
+
+// pages with the buttons and the keypad, forever
+pub fn pager() -> ! {
+    let mut buttons = ButtonEvents::new();
+    let mut keypad = Keypad::new();
+    loop {
+        if let Some(event) = buttons.poll() {
+            if event.kind == ButtonEventKind::Pressed {
+                handle_button(event.button);
+            }
+        }
+        if let Some(event) = keypad.poll() {
+            if event.kind != KeyEventKind::Up {
+                handle_key(event.key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::host::{self, screen_char};
+    use crate::monitor::monitor as cursor;
+    use std::boxed::Box;
+
+    #[test]
+    fn draws_aside_while_paging() {
+        host::reset();
+        cursor::clear_screen();
+        install(Box::leak(Box::new(Scrollback::<4>::new())));
+
+        monitor::set_character(0, 0, b'a');
+        monitor::set_character(0, 1, b'b');
+        monitor::set_character(0, 2, b'c');
+        cursor::scroll_down();
+        cursor::scroll_down();
+        assert_eq!(len(), 2);
+        assert_eq!(screen_char(0, 0), b'c');
+
+        page_up(2);
+        assert!(is_paging());
+        assert_eq!((screen_char(0, 0), screen_char(0, 1)), (b'a', b'b'));
+        assert_eq!(screen_char(0, 2), b'c');
+
+        // drawn directly, kept for the live screen
+        monitor::set_character(5, 0, b'x');
+        assert_eq!(monitor::get_character(5, 0), b'x');
+        assert_eq!(screen_char(5, 0), b' ');
+
+        leave();
+        assert_eq!(screen_char(0, 0), b'c');
+        assert_eq!(screen_char(5, 0), b'x');
+
+        // output on the console goes back
+        page_up(1);
+        cursor::putchar(b'y');
+        assert!(!is_paging());
+        uninstall();
+        assert!(!is_installed());
+    }
+}
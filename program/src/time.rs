@@ -1,21 +1,63 @@
-use core::ops::Sub;
+use core::fmt;
+use core::ops::{Add, Sub};
 use core::time::Duration;
 
-use crate::board::read_u32;
+use crate::mmio::CYCLES;
 
 pub const CPU_FREQUENCY: usize = 20_000_000;
+pub const CYCLES_PER_MICRO: usize = 10;
+
+// The cycle counter wraps every few minutes, but an `Instant` should never
+// go backwards, so the wraps are counted. Right as long as the clock is read
+// at least once per wrap.
+static mut LAST_CYCLES: u32 = 0;
+static mut WRAPS: u32 = 0;
+
+fn cycles() -> u64 {
+    let raw = CYCLES.read();
+    unsafe {
+        if raw < LAST_CYCLES {
+            WRAPS += 1;
+        }
+        LAST_CYCLES = raw;
+        (WRAPS as u64) << 32 | raw as u64
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Instant(Duration);
 
 impl Instant {
     pub fn now() -> Instant {
-        Instant(Duration::from_micros((read_cycles() / 10) as u64))
+        Instant(Duration::from_micros(cycles() / CYCLES_PER_MICRO as u64))
+    }
+
+    pub const fn from_micros(micros: u64) -> Instant {
+        Instant(Duration::from_micros(micros))
+    }
+
+    pub fn as_micros(&self) -> u64 {
+        self.0.as_micros() as u64
     }
 
     pub fn duration_since(&self, earlier: Instant) -> Duration {
         self.0 - earlier.0
     }
+
+    pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().saturating_duration_since(*self)
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+    fn add(self, other: Duration) -> Instant {
+        Instant(self.0 + other)
+    }
 }
 
 impl Sub<Instant> for Instant {
@@ -26,10 +68,173 @@ impl Sub<Instant> for Instant {
 }
 
 pub fn read_cycles() -> usize {
-    unsafe { read_u32(0xFFFFF010) as usize }
+    CYCLES.read() as usize
 }
 
 pub fn sleep(duration: Duration) {
-    let until = Instant::now().0 + duration;
-    while Instant::now().0 < until {}
+    let start = Ticks::now();
+    let cycles = duration_to_cycles(duration);
+    while Ticks::now().cycles_since(start) < cycles {}
+}
+
+// A reading of the raw 32-bit cycle counter. Unlike between `Instant`s, the
+// time between two of them stays right when the counter wraps (about every
+// 7 minutes), as long as they are less than a wrap apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ticks(u32);
+
+impl Ticks {
+    pub fn now() -> Ticks {
+        Ticks(CYCLES.read())
+    }
+
+    pub fn cycles_since(&self, earlier: Ticks) -> u32 {
+        self.0.wrapping_sub(earlier.0)
+    }
+
+    pub fn duration_since(&self, earlier: Ticks) -> Duration {
+        cycles_to_duration(self.cycles_since(earlier))
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Ticks::now().duration_since(*self)
+    }
+}
+
+pub fn cycles_to_duration(cycles: u32) -> Duration {
+    Duration::from_nanos(cycles as u64 * 1000 / CYCLES_PER_MICRO as u64)
+}
+
+// rounded up, at most a full wrap of the counter
+pub fn duration_to_cycles(duration: Duration) -> u32 {
+    let per_second = CYCLES_PER_MICRO as u64 * 1_000_000;
+    let nanos = (duration.subsec_nanos() as u64 * CYCLES_PER_MICRO as u64).div_ceil(1000);
+    let cycles = duration
+        .as_secs()
+        .saturating_mul(per_second)
+        .saturating_add(nanos);
+    cycles.min(u32::MAX as u64) as u32
+}
+
+// when a blocking call gives up, from a `Duration` (counted from now) or an `Instant`
+// it counts cycles from when it was made, so it is at most a wrap of the
+// counter away, see `Ticks`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(Option<(Ticks, u32)>);
+
+impl Deadline {
+    pub const NEVER: Deadline = Deadline(None);
+
+    pub fn at(instant: Instant) -> Deadline {
+        Deadline::after(instant.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn after(duration: Duration) -> Deadline {
+        Deadline(Some((Ticks::now(), duration_to_cycles(duration))))
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.0
+            .is_some_and(|(start, cycles)| Ticks::now().cycles_since(start) >= cycles)
+    }
+
+    // `None` if it never expires
+    pub fn remaining(&self) -> Option<Duration> {
+        self.0.map(|(start, cycles)| {
+            cycles_to_duration(cycles.saturating_sub(Ticks::now().cycles_since(start)))
+        })
+    }
+}
+
+impl From<Duration> for Deadline {
+    fn from(duration: Duration) -> Self {
+        Deadline::after(duration)
+    }
+}
+
+impl From<Instant> for Deadline {
+    fn from(instant: Instant) -> Self {
+        Deadline::at(instant)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimedOut;
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("timed out")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host;
+
+    // the counter wraps after `before` more cycles
+    fn near_wrap(before: u64) {
+        host::reset();
+        host::with_board(|board| board.cycles = (1 << 32) - before);
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(duration_to_cycles(Duration::from_micros(3)), 30);
+        // rounded up
+        assert_eq!(duration_to_cycles(Duration::from_nanos(1)), 1);
+        assert_eq!(duration_to_cycles(Duration::from_secs(1 << 40)), u32::MAX);
+        assert_eq!(cycles_to_duration(25), Duration::from_nanos(2500));
+    }
+
+    #[test]
+    fn instant_across_a_wrap() {
+        near_wrap(1000);
+        let before = Instant::now();
+        host::advance_cycles(2000);
+        let after = Instant::now();
+        assert!(after > before);
+        // about 2001 cycles later, each read takes 100 more
+        assert!(after - before < Duration::from_micros(250));
+    }
+
+    #[test]
+    fn ticks_across_a_wrap() {
+        near_wrap(1000);
+        let start = Ticks::now();
+        host::advance_cycles(5000);
+        let cycles = Ticks::now().cycles_since(start);
+        assert!((5000..5500).contains(&cycles));
+    }
+
+    #[test]
+    fn deadline_across_a_wrap() {
+        near_wrap(5000);
+        let deadline = Deadline::after(Duration::from_millis(1));
+        assert!(!deadline.is_expired());
+        // past the wrap, not yet due
+        host::advance_cycles(8000);
+        assert!(!deadline.is_expired());
+        assert!(deadline.remaining().unwrap() <= Duration::from_micros(200));
+        host::advance_cycles(2000);
+        assert!(deadline.is_expired());
+        assert_eq!(deadline.remaining(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn never() {
+        host::reset();
+        host::advance_cycles(1 << 31);
+        assert!(!Deadline::NEVER.is_expired());
+        assert_eq!(Deadline::NEVER.remaining(), None);
+    }
+
+    #[test]
+    fn sleep_across_a_wrap() {
+        near_wrap(100);
+        let start = Ticks::now();
+        sleep(Duration::from_micros(50));
+        assert!(start.elapsed() >= Duration::from_micros(50));
+        assert!(start.elapsed() < Duration::from_micros(100));
+    }
 }
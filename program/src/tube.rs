@@ -1,5 +1,6 @@
-use crate::board::*;
+use crate::mmio::{TUBE_ENABLE, TUBE_MODE, TUBE_VALUE};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
 pub enum TubeMode {
     Dec = 0,
@@ -7,21 +8,15 @@ pub enum TubeMode {
 }
 
 pub fn set_tube_value(v: u32) {
-    unsafe {
-        write_u32(0xFFFFF038, v);
-    }
+    TUBE_VALUE.write(v);
 }
 
 pub fn set_tube_enable(mask: u8) {
-    unsafe {
-        write_u32(0xFFFFF03C, mask as u32);
-    }
+    TUBE_ENABLE.write(mask as u32);
 }
 
 pub fn set_tube_mode(mode: TubeMode) {
-    unsafe {
-        write_u32(0xFFFFF030, mode as u8 as u32);
-    }
+    TUBE_MODE.write(mode as u8 as u32);
 }
 
 pub fn set_tube_value_option(v: Option<u32>) {
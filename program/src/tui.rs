@@ -0,0 +1,889 @@
+// Building blocks for text screens on any `TextDisplay`: styled spans,
+// bordered panels, labels, progress bars, tables and menus, laid out by
+// splitting rectangles:
+//
+//   let [main, status] = Rect::screen().split(Direction::Vertical, [Size::Fill, Size::Fixed(1)]);
+//   let inner = Panel::new("files").draw(&mut Board, main);
+//   Label::new(format_args!("{} free", free)).draw(&mut Board, status);
+//
+// Everything is clipped to its rectangle. Text goes through
+// `codepage::from_char`, so box drawing characters show up as such.
+
+use core::fmt::{self, Display, Write};
+
+use crate::board::{Button, Keyboard};
+use crate::button::{ButtonEventKind, ButtonEvents};
+use crate::codepage;
+use crate::keypad::{KeyEventKind, Keypad};
+use crate::keys::{Key, KeyDecoder};
+use crate::monitor::{Color, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::platform::TextDisplay;
+use crate::uart;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Style {
+    pub const DEFAULT: Style = Style::new(Color::White, Color::Black);
+
+    pub const fn new(fg: Color, bg: Color) -> Style {
+        Style { fg, bg }
+    }
+
+    pub const fn fg(self, fg: Color) -> Style {
+        Style { fg, ..self }
+    }
+
+    pub const fn bg(self, bg: Color) -> Style {
+        Style { bg, ..self }
+    }
+
+    pub const fn reversed(self) -> Style {
+        Style {
+            fg: self.bg,
+            bg: self.fg,
+        }
+    }
+
+    // the color byte of the VGA
+    pub const fn as_u8(self) -> u8 {
+        (self.bg as u8) << 4 | self.fg as u8
+    }
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style::DEFAULT
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    // side by side
+    Horizontal,
+    // one above the other
+    Vertical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Size {
+    Fixed(usize),
+    // an equal share of what the fixed ones leave
+    Fill,
+}
+
+impl Rect {
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub const fn screen() -> Rect {
+        Rect::new(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    // inside a border
+    pub fn inner(&self) -> Rect {
+        self.shrink(1, 1)
+    }
+
+    pub fn shrink(&self, dx: usize, dy: usize) -> Rect {
+        Rect::new(
+            self.x + dx,
+            self.y + dy,
+            self.width.saturating_sub(2 * dx),
+            self.height.saturating_sub(2 * dy),
+        )
+    }
+
+    // the `y`th line, empty below the bottom
+    pub fn row(&self, y: usize) -> Rect {
+        let height = if y < self.height { 1 } else { 0 };
+        Rect::new(self.x, self.y + y, self.width, height)
+    }
+
+    pub fn centered(&self, width: usize, height: usize) -> Rect {
+        let (width, height) = (width.min(self.width), height.min(self.height));
+        Rect::new(
+            self.x + (self.width - width) / 2,
+            self.y + (self.height - height) / 2,
+            width,
+            height,
+        )
+    }
+
+    // fixed sizes that do not fit are cut short
+    pub fn split<const N: usize>(&self, direction: Direction, sizes: [Size; N]) -> [Rect; N] {
+        let total = match direction {
+            Direction::Horizontal => self.width,
+            Direction::Vertical => self.height,
+        };
+        let fixed: usize = sizes
+            .iter()
+            .map(|size| match size {
+                Size::Fixed(len) => *len,
+                Size::Fill => 0,
+            })
+            .sum();
+        let mut fills = sizes.iter().filter(|size| **size == Size::Fill).count();
+        let mut rest = total.saturating_sub(fixed);
+
+        let mut rects = [*self; N];
+        let mut offset = 0;
+        for (rect, size) in rects.iter_mut().zip(sizes) {
+            let len = match size {
+                Size::Fixed(len) => len,
+                Size::Fill => {
+                    let len = rest / fills;
+                    rest -= len;
+                    fills -= 1;
+                    len
+                }
+            };
+            let len = len.min(total - offset);
+            match direction {
+                Direction::Horizontal => (rect.x, rect.width) = (self.x + offset, len),
+                Direction::Vertical => (rect.y, rect.height) = (self.y + offset, len),
+            }
+            offset += len;
+        }
+        rects
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    // where `len` cells start in `space`
+    fn offset(self, space: usize, len: usize) -> usize {
+        let free = space.saturating_sub(len);
+        match self {
+            Align::Left => 0,
+            Align::Center => free / 2,
+            Align::Right => free,
+        }
+    }
+}
+
+// writes into a row, clipped at `end`
+struct Pen<'d, D: ?Sized> {
+    display: &'d mut D,
+    x: usize,
+    y: usize,
+    end: usize,
+    color: u8,
+}
+
+impl<D: TextDisplay + ?Sized> Write for Pen<'_, D> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            if self.x >= self.end {
+                break;
+            }
+            let glyph = codepage::from_char(c);
+            self.display.set_cell(self.x, self.y, glyph, self.color);
+            self.x += 1;
+        }
+        Ok(())
+    }
+}
+
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
+
+// in cells
+pub fn text_width(text: &impl Display) -> usize {
+    let mut counter = Counter(0);
+    let _ = write!(counter, "{}", text);
+    counter.0
+}
+
+// the part of `rect` on the display
+fn clip(display: &(impl TextDisplay + ?Sized), rect: Rect) -> Rect {
+    let (width, height) = display.size();
+    let (x, y) = (rect.x.min(width), rect.y.min(height));
+    Rect::new(x, y, rect.width.min(width - x), rect.height.min(height - y))
+}
+
+// on the first row of `rect`, returns the cells used
+pub fn draw_text(
+    display: &mut (impl TextDisplay + ?Sized),
+    rect: Rect,
+    text: impl Display,
+    style: Style,
+) -> usize {
+    let rect = clip(display, rect);
+    if rect.is_empty() {
+        return 0;
+    }
+    let mut pen = Pen {
+        display,
+        x: rect.x,
+        y: rect.y,
+        end: rect.x + rect.width,
+        color: style.as_u8(),
+    };
+    let _ = write!(pen, "{}", text);
+    pen.x - rect.x
+}
+
+pub fn fill(display: &mut (impl TextDisplay + ?Sized), rect: Rect, ch: u8, style: Style) {
+    let rect = clip(display, rect);
+    for y in rect.y..rect.y + rect.height {
+        for x in rect.x..rect.x + rect.width {
+            display.set_cell(x, y, ch, style.as_u8());
+        }
+    }
+}
+
+// fills the first row of `rect`, with `text` aligned in it
+fn draw_aligned(
+    display: &mut (impl TextDisplay + ?Sized),
+    rect: Rect,
+    text: impl Display,
+    style: Style,
+    align: Align,
+) {
+    let row = rect.row(0);
+    let offset = align.offset(row.width, text_width(&text));
+    fill(
+        display,
+        Rect {
+            width: offset,
+            ..row
+        },
+        b' ',
+        style,
+    );
+    let [_, rest] = row.split(Direction::Horizontal, [Size::Fixed(offset), Size::Fill]);
+    let len = draw_text(display, rest, text, style);
+    let [_, right] = rest.split(Direction::Horizontal, [Size::Fixed(len), Size::Fill]);
+    fill(display, right, b' ', style);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span<'a> {
+    pub text: &'a str,
+    pub style: Style,
+}
+
+impl<'a> Span<'a> {
+    pub const fn new(text: &'a str, style: Style) -> Span<'a> {
+        Span { text, style }
+    }
+
+    pub const fn raw(text: &'a str) -> Span<'a> {
+        Span::new(text, Style::DEFAULT)
+    }
+}
+
+// one after another on the first row of `rect`, returns the cells used
+pub fn draw_spans(display: &mut (impl TextDisplay + ?Sized), rect: Rect, spans: &[Span]) -> usize {
+    let mut used = 0;
+    for span in spans {
+        let [_, rest] = rect.split(Direction::Horizontal, [Size::Fixed(used), Size::Fill]);
+        used += draw_text(display, rest, span.text, span.style);
+    }
+    used
+}
+
+// a line of text, the rest of the line is cleared
+pub struct Label<T> {
+    text: T,
+    style: Style,
+    align: Align,
+}
+
+impl<T: Display> Label<T> {
+    pub fn new(text: T) -> Label<T> {
+        Label {
+            text,
+            style: Style::DEFAULT,
+            align: Align::Left,
+        }
+    }
+
+    pub fn style(self, style: Style) -> Label<T> {
+        Label { style, ..self }
+    }
+
+    pub fn align(self, align: Align) -> Label<T> {
+        Label { align, ..self }
+    }
+
+    pub fn draw(&self, display: &mut (impl TextDisplay + ?Sized), rect: Rect) {
+        draw_aligned(display, rect, &self.text, self.style, self.align);
+    }
+}
+
+// a border with a title on top, the inside is left alone
+pub struct Panel<'a> {
+    title: &'a str,
+    border: Style,
+    title_style: Style,
+}
+
+impl<'a> Panel<'a> {
+    pub fn new(title: &'a str) -> Panel<'a> {
+        Panel {
+            title,
+            border: Style::DEFAULT,
+            title_style: Style::DEFAULT,
+        }
+    }
+
+    pub fn border_style(self, border: Style) -> Panel<'a> {
+        Panel { border, ..self }
+    }
+
+    pub fn title_style(self, title_style: Style) -> Panel<'a> {
+        Panel {
+            title_style,
+            ..self
+        }
+    }
+
+    // returns the inside
+    pub fn draw(&self, display: &mut (impl TextDisplay + ?Sized), rect: Rect) -> Rect {
+        let rect = clip(display, rect);
+        if rect.width < 2 || rect.height < 2 {
+            return Rect::new(rect.x, rect.y, 0, 0);
+        }
+        let color = self.border.as_u8();
+        let (right, bottom) = (rect.x + rect.width - 1, rect.y + rect.height - 1);
+
+        // the title, with a space on each side, centered on the top line
+        let title = match self.title.chars().count() {
+            0 => 0,
+            len => (len + 2).min(rect.width - 2),
+        };
+        let title_x = rect.x + 1 + Align::Center.offset(rect.width - 2, title);
+        if title > 0 {
+            let text = Rect::new(title_x, rect.y, title, 1);
+            draw_aligned(display, text, self.title, self.title_style, Align::Center);
+        }
+
+        for x in rect.x + 1..right {
+            if !(title_x..title_x + title).contains(&x) {
+                display.set_cell(x, rect.y, codepage::HORIZONTAL, color);
+            }
+            display.set_cell(x, bottom, codepage::HORIZONTAL, color);
+        }
+        for y in rect.y + 1..bottom {
+            display.set_cell(rect.x, y, codepage::VERTICAL, color);
+            display.set_cell(right, y, codepage::VERTICAL, color);
+        }
+        display.set_cell(rect.x, rect.y, codepage::DOWN_RIGHT, color);
+        display.set_cell(right, rect.y, codepage::DOWN_LEFT, color);
+        display.set_cell(rect.x, bottom, codepage::UP_RIGHT, color);
+        display.set_cell(right, bottom, codepage::UP_LEFT, color);
+
+        rect.inner()
+    }
+}
+
+// a bar on one line, with the percentage in the middle
+pub struct ProgressBar {
+    value: u32,
+    max: u32,
+    filled: Style,
+    empty: Style,
+}
+
+impl ProgressBar {
+    // a `max` of 0 is full
+    pub fn new(value: u32, max: u32) -> ProgressBar {
+        ProgressBar {
+            value: value.min(max),
+            max,
+            filled: Style::DEFAULT.reversed(),
+            empty: Style::DEFAULT,
+        }
+    }
+
+    pub fn filled_style(self, filled: Style) -> ProgressBar {
+        ProgressBar { filled, ..self }
+    }
+
+    pub fn empty_style(self, empty: Style) -> ProgressBar {
+        ProgressBar { empty, ..self }
+    }
+
+    pub fn draw(&self, display: &mut (impl TextDisplay + ?Sized), rect: Rect) {
+        let row = clip(display, rect.row(0));
+        let (filled, percent) = match self.max {
+            0 => (row.width, 100),
+            max => (
+                (self.value as u64 * row.width as u64 / max as u64) as usize,
+                (self.value as u64 * 100 / max as u64) as u32,
+            ),
+        };
+
+        let mut text = [b' '; 4];
+        let len = if percent >= 100 {
+            3
+        } else if percent >= 10 {
+            2
+        } else {
+            1
+        };
+        let mut n = percent;
+        for i in (0..len).rev() {
+            text[i] = b'0' + (n % 10) as u8;
+            n /= 10;
+        }
+        text[len] = b'%';
+        let start = Align::Center.offset(row.width, len + 1);
+
+        for i in 0..row.width {
+            let ch = match i.checked_sub(start) {
+                Some(j) if j <= len => text[j],
+                _ => b' ',
+            };
+            let style = if i < filled { self.filled } else { self.empty };
+            display.set_cell(row.x + i, row.y, ch, style.as_u8());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Column<'a> {
+    pub title: &'a str,
+    pub width: usize,
+    pub align: Align,
+}
+
+impl<'a> Column<'a> {
+    pub const fn new(title: &'a str, width: usize) -> Column<'a> {
+        Column {
+            title,
+            width,
+            align: Align::Left,
+        }
+    }
+
+    pub const fn align(self, align: Align) -> Column<'a> {
+        Column { align, ..self }
+    }
+}
+
+// columns of fixed width, a space apart
+pub struct Table<'a> {
+    columns: &'a [Column<'a>],
+    header: bool,
+    style: Style,
+    header_style: Style,
+    selected_style: Style,
+    selected: Option<usize>,
+}
+
+impl<'a> Table<'a> {
+    pub fn new(columns: &'a [Column<'a>]) -> Table<'a> {
+        Table {
+            columns,
+            header: true,
+            style: Style::DEFAULT,
+            header_style: Style::DEFAULT.fg(Color::BrightWhite),
+            selected_style: Style::DEFAULT.reversed(),
+            selected: None,
+        }
+    }
+
+    pub fn header(self, header: bool) -> Table<'a> {
+        Table { header, ..self }
+    }
+
+    pub fn style(self, style: Style) -> Table<'a> {
+        Table { style, ..self }
+    }
+
+    pub fn header_style(self, header_style: Style) -> Table<'a> {
+        Table {
+            header_style,
+            ..self
+        }
+    }
+
+    pub fn selected_style(self, selected_style: Style) -> Table<'a> {
+        Table {
+            selected_style,
+            ..self
+        }
+    }
+
+    // the row drawn in `selected_style`
+    pub fn select(self, selected: Option<usize>) -> Table<'a> {
+        Table { selected, ..self }
+    }
+
+    fn draw_row<'c>(
+        &self,
+        display: &mut (impl TextDisplay + ?Sized),
+        rect: Rect,
+        cell: impl Fn(usize) -> Option<&'c dyn Display>,
+        style: Style,
+    ) {
+        let mut rest = rect;
+        for (i, column) in self.columns.iter().enumerate() {
+            let [area, gap, next] = rest.split(
+                Direction::Horizontal,
+                [Size::Fixed(column.width), Size::Fixed(1), Size::Fill],
+            );
+            match cell(i) {
+                Some(text) => draw_aligned(display, area, text, style, column.align),
+                None => fill(display, area, b' ', style),
+            }
+            if i + 1 < self.columns.len() {
+                fill(display, gap, b' ', style);
+            }
+            rest = next;
+        }
+    }
+
+    // rows that do not fit are not drawn, the lines left over are cleared
+    pub fn draw(
+        &self,
+        display: &mut (impl TextDisplay + ?Sized),
+        rect: Rect,
+        rows: &[&[&dyn Display]],
+    ) {
+        let mut y = 0;
+        if self.header {
+            let titles = |i: usize| Some(&self.columns[i].title as &dyn Display);
+            self.draw_row(display, rect.row(0), titles, self.header_style);
+            y += 1;
+        }
+        for (i, cells) in rows.iter().enumerate() {
+            if y >= rect.height {
+                return;
+            }
+            let style = if self.selected == Some(i) {
+                self.selected_style
+            } else {
+                self.style
+            };
+            self.draw_row(display, rect.row(y), |i| cells.get(i).copied(), style);
+            y += 1;
+        }
+        let [_, rest] = rect.split(Direction::Vertical, [Size::Fixed(y), Size::Fill]);
+        fill(display, rest, b' ', self.style);
+    }
+}
+
+// a list to choose from: Up/Down and Center on the buttons, A/B and # on
+// the keypad, the arrows and Enter on the UART, or the item's number
+pub struct Menu<'a> {
+    items: &'a [&'a str],
+    selected: usize,
+    style: Style,
+    selected_style: Style,
+}
+
+impl<'a> Menu<'a> {
+    pub fn new(items: &'a [&'a str]) -> Menu<'a> {
+        Menu {
+            items,
+            selected: 0,
+            style: Style::DEFAULT,
+            selected_style: Style::DEFAULT.reversed(),
+        }
+    }
+
+    pub fn style(self, style: Style) -> Menu<'a> {
+        Menu { style, ..self }
+    }
+
+    pub fn selected_style(self, selected_style: Style) -> Menu<'a> {
+        Menu {
+            selected_style,
+            ..self
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.items.len().saturating_sub(1));
+    }
+
+    // both wrap around
+    pub fn up(&mut self) {
+        self.selected = match self.selected {
+            0 => self.items.len().saturating_sub(1),
+            i => i - 1,
+        };
+    }
+
+    pub fn down(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len().max(1);
+    }
+
+    fn choose(&self) -> Option<usize> {
+        (self.selected < self.items.len()).then_some(self.selected)
+    }
+
+    // 1 is the first item
+    fn choose_number(&mut self, n: u32) -> Option<usize> {
+        let index = (n as usize).checked_sub(1)?;
+        if index >= self.items.len() {
+            return None;
+        }
+        self.selected = index;
+        Some(index)
+    }
+
+    // these return the chosen item, if any
+    pub fn handle_button(&mut self, button: Button) -> Option<usize> {
+        match button {
+            Button::Up => self.up(),
+            Button::Down => self.down(),
+            Button::Center => return self.choose(),
+            _ => {}
+        }
+        None
+    }
+
+    pub fn handle_key(&mut self, key: Keyboard) -> Option<usize> {
+        match key {
+            Keyboard::A => self.up(),
+            Keyboard::B => self.down(),
+            Keyboard::Number => return self.choose(),
+            key => return self.choose_number(key.as_number()?),
+        }
+        None
+    }
+
+    pub fn handle_uart(&mut self, key: Key) -> Option<usize> {
+        match key {
+            Key::Up => self.up(),
+            Key::Down => self.down(),
+            Key::Enter => return self.choose(),
+            Key::Char(c) => return self.choose_number(c.to_digit(10)?),
+            _ => {}
+        }
+        None
+    }
+
+    // scrolls to keep the selected item in view
+    pub fn draw(&self, display: &mut (impl TextDisplay + ?Sized), rect: Rect) {
+        let top = (self.selected + 1).saturating_sub(rect.height);
+        for y in 0..rect.height {
+            let row = rect.row(y);
+            match self.items.get(top + y) {
+                Some(item) if top + y == self.selected => Label::new(item)
+                    .style(self.selected_style)
+                    .draw(display, row),
+                Some(item) => Label::new(item).style(self.style).draw(display, row),
+                None => fill(display, row, b' ', self.style),
+            }
+        }
+    }
+
+    // draws and waits until an item is chosen, there has to be one
+    pub fn run(&mut self, display: &mut (impl TextDisplay + ?Sized), rect: Rect) -> usize {
+        assert!(
+            !self.items.is_empty(),
+            "tui: a menu needs an item to choose"
+        );
+        let mut buttons = ButtonEvents::new();
+        let mut keypad = Keypad::new();
+        let mut keys = KeyDecoder::new();
+        let mut drawn = None;
+        loop {
+            if drawn != Some(self.selected) {
+                self.draw(display, rect);
+                display.flush();
+                drawn = Some(self.selected);
+            }
+
+            let mut chosen = None;
+            if let Some(event) = buttons.poll() {
+                if event.kind == ButtonEventKind::Pressed {
+                    chosen = self.handle_button(event.button);
+                }
+            }
+            if let Some(event) = keypad.poll() {
+                if event.kind != KeyEventKind::Up {
+                    chosen = chosen.or(self.handle_key(event.key));
+                }
+            }
+            if let Some(key) = uart::try_read().and_then(|byte| keys.push(byte)) {
+                chosen = chosen.or(self.handle_uart(key));
+            }
+            if let Some(index) = chosen {
+                return index;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{self, push_uart_input};
+
+    struct Grid {
+        cells: [[(u8, u8); 8]; 4],
+    }
+
+    impl Grid {
+        fn new() -> Grid {
+            Grid {
+                cells: [[(b' ', 0); 8]; 4],
+            }
+        }
+
+        fn line(&self, y: usize) -> [u8; 8] {
+            self.cells[y].map(|(ch, _)| ch)
+        }
+    }
+
+    impl TextDisplay for Grid {
+        fn size(&self) -> (usize, usize) {
+            (8, 4)
+        }
+
+        fn set_cell(&mut self, x: usize, y: usize, ch: u8, color: u8) {
+            self.cells[y][x] = (ch, color);
+        }
+
+        fn cell(&self, x: usize, y: usize) -> (u8, u8) {
+            self.cells[y][x]
+        }
+    }
+
+    fn widths<const N: usize>(rects: [Rect; N]) -> [(usize, usize); N] {
+        rects.map(|rect| (rect.x, rect.width))
+    }
+
+    #[test]
+    fn split() {
+        let rect = Rect::new(2, 1, 10, 5);
+        let h = Direction::Horizontal;
+
+        assert_eq!(
+            widths(rect.split(h, [Size::Fixed(3), Size::Fill, Size::Fixed(2)])),
+            [(2, 3), (5, 5), (10, 2)]
+        );
+        // the remainder goes to the last fills
+        assert_eq!(
+            widths(rect.split(h, [Size::Fill, Size::Fill, Size::Fill])),
+            [(2, 3), (5, 3), (8, 4)]
+        );
+        assert_eq!(
+            widths(rect.split(h, [Size::Fill, Size::Fixed(4), Size::Fill])),
+            [(2, 3), (5, 4), (9, 3)]
+        );
+        // fixed sizes that do not fit are cut short, and leave nothing to fill
+        assert_eq!(
+            widths(rect.split(h, [Size::Fixed(6), Size::Fill, Size::Fixed(6)])),
+            [(2, 6), (8, 0), (8, 4)]
+        );
+        assert_eq!(
+            widths(rect.split(h, [Size::Fixed(20), Size::Fixed(1)])),
+            [(2, 10), (12, 0)]
+        );
+
+        let [top, bottom] = rect.split(Direction::Vertical, [Size::Fill, Size::Fixed(1)]);
+        assert_eq!(top, Rect::new(2, 1, 10, 4));
+        assert_eq!(bottom, Rect::new(2, 5, 10, 1));
+        assert!(Rect::new(0, 0, 0, 3)
+            .split(h, [Size::Fill, Size::Fill])
+            .iter()
+            .all(Rect::is_empty));
+    }
+
+    #[test]
+    fn menu_wraps_around() {
+        let items = ["a", "b", "c"];
+        let mut menu = Menu::new(&items);
+        menu.up();
+        assert_eq!(menu.selected(), 2);
+        menu.down();
+        assert_eq!(menu.selected(), 0);
+        assert_eq!(menu.handle_button(Button::Up), None);
+        assert_eq!(menu.handle_key(Keyboard::B), None);
+        assert_eq!(menu.selected(), 0);
+        assert_eq!(menu.handle_uart(Key::Down), None);
+        assert_eq!(menu.handle_button(Button::Center), Some(1));
+        menu.select(10);
+        assert_eq!(menu.selected(), 2);
+    }
+
+    #[test]
+    fn menu_numbers() {
+        let items = ["a", "b", "c"];
+        let mut menu = Menu::new(&items);
+        assert_eq!(menu.handle_key(Keyboard::_3), Some(2));
+        assert_eq!(menu.selected(), 2);
+        assert_eq!(menu.handle_key(Keyboard::_0), None);
+        assert_eq!(menu.handle_key(Keyboard::_4), None);
+        assert_eq!(menu.handle_uart(Key::Char('2')), Some(1));
+        assert_eq!(menu.handle_uart(Key::Char('x')), None);
+        assert_eq!(menu.selected(), 1);
+    }
+
+    #[test]
+    fn menu_scrolls() {
+        let items = ["one", "two", "three", "four", "five"];
+        let mut menu = Menu::new(&items);
+        let mut grid = Grid::new();
+        let rect = Rect::new(0, 0, 8, 3);
+
+        menu.draw(&mut grid, rect);
+        assert_eq!(&grid.line(0)[..3], b"one");
+        menu.select(4);
+        menu.draw(&mut grid, rect);
+        assert_eq!(&grid.line(0)[..5], b"three");
+        assert_eq!(&grid.line(2)[..4], b"five");
+        assert_eq!(grid.cell(0, 2).1, Style::DEFAULT.reversed().as_u8());
+    }
+
+    #[test]
+    fn menu_runs() {
+        host::reset();
+        uart::discard_input();
+        let items = ["a", "b", "c"];
+        let mut grid = Grid::new();
+        push_uart_input(b"\x1b[A\x1b[A\r");
+        assert_eq!(Menu::new(&items).run(&mut grid, Rect::new(0, 0, 8, 4)), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "a menu needs an item")]
+    fn empty_menu() {
+        Menu::new(&[]).run(&mut Grid::new(), Rect::new(0, 0, 8, 4));
+    }
+}
@@ -1,33 +1,182 @@
-use crate::board::*;
+use core::fmt;
+use core::ptr::addr_of_mut;
 
+use crate::mmio::{UART_RX, UART_RX_READY, UART_TX, UART_TX_READY};
+use crate::ring::RingBuffer;
+use crate::time::{Deadline, TimedOut};
+
+// The hardware FIFO only holds 128 bytes, so received bytes are moved into a
+// larger software ring whenever the driver is used, or `poll` is called.
+// Bytes that cannot be sent right away are queued, and sent by later calls.
+pub const RX_BUFFER_SIZE: usize = 2048;
+pub const TX_BUFFER_SIZE: usize = 256;
+
+static mut RX_BUFFER: RingBuffer<u8, RX_BUFFER_SIZE> = RingBuffer::new();
+static mut TX_BUFFER: RingBuffer<u8, TX_BUFFER_SIZE> = RingBuffer::new();
+static mut STATS: Stats = Stats::new();
+
+// a single core, and no interrupt handler touches the buffers, so two of
+// these references are never used at once
+fn rx_buffer() -> &'static mut RingBuffer<u8, RX_BUFFER_SIZE> {
+    unsafe { &mut *addr_of_mut!(RX_BUFFER) }
+}
+
+fn tx_buffer() -> &'static mut RingBuffer<u8, TX_BUFFER_SIZE> {
+    unsafe { &mut *addr_of_mut!(TX_BUFFER) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    // received bytes dropped because the RX ring was full
+    pub rx_overflows: u32,
+    // bytes rejected by `try_write` because the TX queue was full
+    pub tx_overflows: u32,
+    // the most bytes ever waiting in the RX ring
+    pub rx_high_water: usize,
+}
+
+impl Stats {
+    const fn new() -> Stats {
+        Stats {
+            rx_overflows: 0,
+            tx_overflows: 0,
+            rx_high_water: 0,
+        }
+    }
+}
+
+// the UART as a value, for `fmt::Write` and the traits in `hal`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Uart;
+
+impl fmt::Write for Uart {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.bytes().for_each(write);
+        Ok(())
+    }
+}
+
+// whether the hardware can take a byte
 #[inline]
 pub fn output_ready() -> bool {
-    unsafe { read_u32(0xFFFFF00C) > 0 }
+    UART_TX_READY.read() > 0
 }
 
-#[inline]
+// whether `read` would return immediately
 pub fn input_ready() -> bool {
-    unsafe { read_u32(0xFFFFF008) > 0 }
+    poll();
+    !rx_buffer().is_empty()
 }
 
+// bypass the buffers, and do not check the ready bits
+/// # Safety
+///
+/// The hardware FIFO has to have room (`output_ready`), or the byte is
+/// lost. It also skips anything still queued in the TX buffer.
 #[inline]
 pub unsafe fn write_unchecked(data: u8) {
-    unsafe { write_u32(0xFFFFF004, data as u32) }
+    UART_TX.write(data as u32)
 }
 
+/// # Safety
+///
+/// The hardware FIFO has to hold a byte, otherwise the last byte is read
+/// again. Bytes already moved to the RX buffer are skipped.
 #[inline]
 pub unsafe fn read_unchecked() -> u8 {
-    unsafe { read_u32(0xFFFFF000) as u8 }
+    UART_RX.read() as u8
+}
+
+// moves received bytes into the RX ring, and sends queued bytes
+pub fn poll() {
+    unsafe {
+        while UART_RX_READY.read() > 0 {
+            if rx_buffer().push(read_unchecked()).is_err() {
+                STATS.rx_overflows += 1;
+            }
+        }
+        STATS.rx_high_water = STATS.rx_high_water.max(rx_buffer().len());
+
+        while !tx_buffer().is_empty() && output_ready() {
+            write_unchecked(tx_buffer().pop().unwrap());
+        }
+    }
+}
+
+pub fn try_read() -> Option<u8> {
+    poll();
+    rx_buffer().pop()
+}
+
+// `None` if the TX queue is full, the byte is dropped then
+pub fn try_write(data: u8) -> Option<()> {
+    poll();
+    unsafe {
+        if tx_buffer().is_empty() && output_ready() {
+            write_unchecked(data);
+        } else if tx_buffer().push(data).is_err() {
+            STATS.tx_overflows += 1;
+            return None;
+        }
+    }
+    Some(())
 }
 
-#[inline]
 pub fn write(data: u8) {
-    while !output_ready() {}
-    unsafe { write_unchecked(data) }
+    while tx_buffer().is_full() {
+        poll();
+    }
+    let _ = try_write(data);
 }
 
-#[inline]
 pub fn read() -> u8 {
-    while !input_ready() {}
-    unsafe { read_unchecked() }
+    loop {
+        if let Some(data) = try_read() {
+            return data;
+        }
+    }
+}
+
+pub fn read_timeout(deadline: impl Into<Deadline>) -> Result<u8, TimedOut> {
+    let deadline = deadline.into();
+    loop {
+        if let Some(data) = try_read() {
+            return Ok(data);
+        }
+        if deadline.is_expired() {
+            return Err(TimedOut);
+        }
+    }
+}
+
+// blocks until every queued byte is handed to the hardware
+pub fn flush() {
+    while !tx_buffer().is_empty() {
+        poll();
+    }
+}
+
+// bytes waiting in the RX ring
+pub fn rx_pending() -> usize {
+    poll();
+    rx_buffer().len()
+}
+
+// bytes waiting in the TX queue
+pub fn tx_pending() -> usize {
+    tx_buffer().len()
+}
+
+// drops everything received so far
+pub fn discard_input() {
+    poll();
+    rx_buffer().clear()
+}
+
+pub fn stats() -> Stats {
+    unsafe { STATS }
+}
+
+pub fn reset_stats() {
+    unsafe { STATS = Stats::new() }
 }
@@ -0,0 +1,162 @@
+// Decodes UTF-8 a byte at a time, as it comes from the UART.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf8Error {
+    // not valid UTF-8, the bytes so far are dropped
+    Invalid,
+    // the character was cut short by this byte, which is not used,
+    // push it again
+    Interrupted,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Utf8Decoder {
+    buf: [u8; 4],
+    len: usize,
+    need: usize,
+}
+
+impl Utf8Decoder {
+    pub const fn new() -> Utf8Decoder {
+        Utf8Decoder {
+            buf: [0; 4],
+            len: 0,
+            need: 0,
+        }
+    }
+
+    // in the middle of a character
+    pub fn is_pending(&self) -> bool {
+        self.len > 0
+    }
+
+    pub fn reset(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn push(&mut self, byte: u8) -> Option<Result<char, Utf8Error>> {
+        if self.len == 0 {
+            self.need = match byte {
+                0x00..=0x7F => return Some(Ok(byte as char)),
+                0xC2..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF4 => 4,
+                _ => return Some(Err(Utf8Error::Invalid)),
+            };
+            self.buf[0] = byte;
+            self.len = 1;
+            return None;
+        }
+
+        if byte & 0xC0 != 0x80 {
+            self.reset();
+            return Some(Err(Utf8Error::Interrupted));
+        }
+        self.buf[self.len] = byte;
+        self.len += 1;
+        if self.len < self.need {
+            return None;
+        }
+
+        self.reset();
+        // also rejects overlong forms and surrogates
+        let c = core::str::from_utf8(&self.buf[..self.need])
+            .ok()
+            .and_then(|s| s.chars().next());
+        Some(c.ok_or(Utf8Error::Invalid))
+    }
+}
+
+impl Default for Utf8Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the results of pushing `bytes`, a byte after `Interrupted` is pushed again
+    fn decode(bytes: &[u8], out: &mut [Result<char, Utf8Error>; 8]) -> usize {
+        let mut decoder = Utf8Decoder::new();
+        let mut len = 0;
+        for &byte in bytes {
+            let mut result = decoder.push(byte);
+            if result == Some(Err(Utf8Error::Interrupted)) {
+                out[len] = Err(Utf8Error::Interrupted);
+                len += 1;
+                result = decoder.push(byte);
+            }
+            if let Some(result) = result {
+                out[len] = result;
+                len += 1;
+            }
+        }
+        assert!(!decoder.is_pending());
+        len
+    }
+
+    fn check(bytes: &[u8], expected: &[Result<char, Utf8Error>]) {
+        let mut out = [Ok('\0'); 8];
+        let len = decode(bytes, &mut out);
+        assert_eq!(&out[..len], expected, "{:02x?}", bytes);
+    }
+
+    #[test]
+    fn valid() {
+        for c in [
+            'a',
+            '\0',
+            '\x7f',
+            'é',
+            '\u{7FF}',
+            '\u{800}',
+            '│',
+            '\u{FFFF}',
+            '\u{10000}',
+            '\u{10FFFF}',
+        ] {
+            let mut buf = [0; 4];
+            check(c.encode_utf8(&mut buf).as_bytes(), &[Ok(c)]);
+        }
+        check("a─b".as_bytes(), &[Ok('a'), Ok('─'), Ok('b')]);
+    }
+
+    #[test]
+    fn invalid() {
+        use Utf8Error::*;
+
+        // stray continuation bytes, and lead bytes that never start a character
+        check(b"\x80\xBF", &[Err(Invalid), Err(Invalid)]);
+        check(b"\xF5\xFF", &[Err(Invalid), Err(Invalid)]);
+        // overlong forms
+        check(b"\xC0\x80", &[Err(Invalid), Err(Invalid)]);
+        check(b"\xC1\xBF", &[Err(Invalid), Err(Invalid)]);
+        check(b"\xE0\x80\xAF", &[Err(Invalid)]);
+        check(b"\xE0\x9F\xBF", &[Err(Invalid)]);
+        check(b"\xF0\x80\x80\xAF", &[Err(Invalid)]);
+        check(b"\xF0\x8F\xBF\xBF", &[Err(Invalid)]);
+        // surrogates, and past U+10FFFF
+        check(b"\xED\xA0\x80", &[Err(Invalid)]);
+        check(b"\xED\xBF\xBF", &[Err(Invalid)]);
+        check(b"\xF4\x90\x80\x80", &[Err(Invalid)]);
+        // the decoder starts over after an invalid character
+        check(b"\xE0\x80\x80x", &[Err(Invalid), Ok('x')]);
+    }
+
+    #[test]
+    fn interrupted() {
+        use Utf8Error::*;
+
+        check(b"\xC3a", &[Err(Interrupted), Ok('a')]);
+        check(b"\xE2\x94\xC3\xA9", &[Err(Interrupted), Ok('é')]);
+        check(b"\xF0\x9F\x98\x1b", &[Err(Interrupted), Ok('\x1b')]);
+
+        let mut decoder = Utf8Decoder::new();
+        assert_eq!(decoder.push(0xE2), None);
+        assert!(decoder.is_pending());
+        decoder.reset();
+        assert_eq!(decoder.push(b'z'), Some(Ok('z')));
+    }
+}
@@ -0,0 +1,267 @@
+// A rectangle of the screen with its own cursor and color, so that parts
+// of a program can print without getting in each other's way:
+//
+//   let mut status = Window::new(0, 0, SCREEN_WIDTH, 1);
+//   let mut log = Window::new(0, 30, SCREEN_WIDTH, 7);
+//   write!(log, "score {}\r\n", score);
+//
+// Text wraps at the right edge, or is clipped with `set_wrap(false)`, and
+// the window scrolls at the bottom. Colors, cursor movement and erasing
+// work through the same escape sequences as on the console.
+
+use core::fmt;
+
+use crate::ansi::{Action, Attributes, Csi, Parser};
+use crate::codepage;
+use crate::monitor::{self, SCREEN_HEIGHT, SCREEN_WIDTH, TAB_WIDTH};
+
+pub struct Window {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    // relative to the window, `cursor_x == width` waits for the next
+    // character to wrap
+    cursor_x: usize,
+    cursor_y: usize,
+    attributes: Attributes,
+    wrap: bool,
+    parser: Parser,
+}
+
+impl Window {
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Window {
+        assert!(width > 0 && height > 0);
+        assert!(x + width <= SCREEN_WIDTH && y + height <= SCREEN_HEIGHT);
+        Window {
+            x,
+            y,
+            width,
+            height,
+            cursor_x: 0,
+            cursor_y: 0,
+            attributes: Attributes::DEFAULT,
+            wrap: true,
+            parser: Parser::new(),
+        }
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.cursor_x.min(self.width - 1), self.cursor_y)
+    }
+
+    // clamped to the window
+    pub fn set_cursor(&mut self, x: usize, y: usize) {
+        self.cursor_x = x.min(self.width - 1);
+        self.cursor_y = y.min(self.height - 1);
+    }
+
+    pub fn color(&self) -> u8 {
+        self.attributes.color
+    }
+
+    pub fn set_color(&mut self, color: u8) {
+        self.attributes = Attributes { color, bold: false };
+    }
+
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    pub fn clear(&mut self) {
+        self.erase(0, self.width * self.height);
+        self.cursor_x = 0;
+        self.cursor_y = 0;
+        monitor::flush();
+    }
+
+    pub fn clear_line(&mut self) {
+        let line = self.cursor_y * self.width;
+        self.erase(line, line + self.width);
+        self.cursor_x = 0;
+        monitor::flush();
+    }
+
+    pub fn putchar(&mut self, ch: u8) {
+        if let Some(action) = self.parser.push(ch) {
+            self.perform(action);
+        }
+    }
+
+    // cells are numbered row by row, within the window
+    fn erase(&self, from: usize, to: usize) {
+        for i in from..to {
+            let (x, y) = (self.x + i % self.width, self.y + i / self.width);
+            monitor::set_character(x, y, b' ');
+            monitor::set_color(x, y, self.attributes.color);
+        }
+    }
+
+    fn scroll(&self) {
+        for y in self.y..self.y + self.height - 1 {
+            for x in self.x..self.x + self.width {
+                monitor::set_character(x, y, monitor::get_character(x, y + 1));
+                monitor::set_color(x, y, monitor::get_color(x, y + 1));
+            }
+        }
+        let last = (self.height - 1) * self.width;
+        self.erase(last, last + self.width);
+    }
+
+    fn newline(&mut self) {
+        self.cursor_x = 0;
+        if self.cursor_y + 1 == self.height {
+            self.scroll();
+        } else {
+            self.cursor_y += 1;
+        }
+    }
+
+    fn put_glyph(&mut self, glyph: u8) {
+        if self.cursor_x == self.width {
+            if !self.wrap {
+                return;
+            }
+            self.newline();
+        }
+        let (x, y) = (self.x + self.cursor_x, self.y + self.cursor_y);
+        monitor::set_character(x, y, glyph);
+        monitor::set_color(x, y, self.attributes.color);
+        self.cursor_x += 1;
+    }
+
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::Print(glyph) => self.put_glyph(glyph),
+            Action::Control(b'\r') => self.cursor_x = 0,
+            Action::Control(b'\n') => self.newline(),
+            Action::Control(b'\x08') => {
+                self.cursor_x = self.cursor_x.min(self.width - 1).saturating_sub(1)
+            }
+            Action::Control(b'\t') => {
+                self.cursor_x = ((self.cursor_x / TAB_WIDTH + 1) * TAB_WIDTH).min(self.width - 1)
+            }
+            Action::Csi(csi) => self.perform_csi(&csi),
+            _ => {}
+        }
+    }
+
+    fn perform_csi(&mut self, csi: &Csi) {
+        if csi.private {
+            return;
+        }
+
+        let (x, y) = self.cursor();
+        let n = csi.param(0, 1) as usize;
+        match csi.command {
+            b'A' => self.set_cursor(x, y.saturating_sub(n)),
+            b'B' => self.set_cursor(x, y + n),
+            b'C' => self.set_cursor(x + n, y),
+            b'D' => self.set_cursor(x.saturating_sub(n), y),
+            b'H' | b'f' => self.set_cursor(csi.param(1, 1) as usize - 1, n - 1),
+            b'J' => {
+                let cursor = y * self.width + x;
+                match csi.param(0, 0) {
+                    0 => self.erase(cursor, self.width * self.height),
+                    1 => self.erase(0, cursor + 1),
+                    2 | 3 => self.erase(0, self.width * self.height),
+                    _ => {}
+                }
+            }
+            b'K' => {
+                let line = y * self.width;
+                match csi.param(0, 0) {
+                    0 => self.erase(line + x, line + self.width),
+                    1 => self.erase(line, line + x + 1),
+                    2 => self.erase(line, line + self.width),
+                    _ => {}
+                }
+            }
+            b'm' => self.attributes.apply(csi.params()),
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Write for Window {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // escape sequences are ASCII, so the glyphs of the code page go
+        // through the parser like any other byte
+        for glyph in s.chars().map(codepage::from_char) {
+            self.putchar(glyph);
+        }
+        monitor::flush();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{self, screen_char, screen_line};
+    use core::fmt::Write;
+
+    // fills the top left of the screen, to see what a window leaves alone
+    fn fresh() -> Window {
+        host::reset();
+        for y in 0..4 {
+            for x in 0..8 {
+                monitor::set_character(x, y, b'#');
+            }
+        }
+        Window::new(2, 1, 4, 2)
+    }
+
+    fn lines() -> [std::string::String; 4] {
+        core::array::from_fn(screen_line)
+    }
+
+    #[test]
+    fn wraps_or_clips() {
+        let mut window = fresh();
+        write!(window, "abcdef").unwrap();
+        assert_eq!(lines(), ["########", "##abcd##", "##ef####", "########"]);
+        assert_eq!(window.cursor(), (2, 1));
+
+        let mut window = fresh();
+        window.set_wrap(false);
+        write!(window, "abcdef").unwrap();
+        assert_eq!(lines(), ["########", "##abcd##", "########", "########"]);
+        assert_eq!(window.cursor(), (3, 0));
+    }
+
+    #[test]
+    fn scrolls_inside() {
+        let mut window = fresh();
+        window.clear();
+        write!(window, "ab\r\ncd\r\nef").unwrap();
+        assert_eq!(lines(), ["########", "##cd  ##", "##ef  ##", "########"]);
+    }
+
+    #[test]
+    fn escapes_are_clipped() {
+        let mut window = fresh();
+        write!(window, "\x1b[9;9Hx").unwrap();
+        assert_eq!(screen_char(5, 2), b'x');
+
+        write!(window, "\x1b[2J").unwrap();
+        assert_eq!(lines(), ["########", "##    ##", "##    ##", "########"]);
+
+        write!(window, "\x1b[1;2Hab\x1b[1;3H\x1b[K").unwrap();
+        assert_eq!(lines(), ["########", "## a  ##", "##    ##", "########"]);
+
+        // a sequence cut in two, followed by a character outside ASCII
+        write!(window, "\x1b[2").unwrap();
+        write!(window, ";1H│").unwrap();
+        assert_eq!(screen_char(2, 2), codepage::VERTICAL);
+        assert_eq!(window.cursor(), (1, 1));
+    }
+}